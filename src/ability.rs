@@ -0,0 +1,272 @@
+use std::cmp;
+use rand::Rng;
+use tcod::colors::*;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde::de::IgnoredAny;
+
+use crate::object::{Object, projectile_path, closest_hostile, award_xp, is_blocked};
+use crate::monster::{monster_def, create_monster};
+use crate::monster_ai::{AiChange, change_ai};
+use crate::game::Game;
+use crate::magic::{Spell, monster_cast};
+use crate::boss::boss_abilities;
+
+const PLAYER: usize = 0;
+const BREATH_CHANCE: i32 = 30; // percent chance to use a ready breath attack on a target in range
+const FLEE_TURNS: i32 = 20;    // how long a thief runs for after stealing
+
+// special powers a monster kind can have, any number of them can be combined
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Ability {
+    // heals a little every turn
+    Regenerate { amount: i32 },
+    // hits poison the target for a while
+    PoisonBite { damage: i32, num_turns: i32 },
+    // burns hostiles standing next to it every turn
+    FireAura { damage: i32 },
+    // hits on the player steal gold, then it runs off
+    StealGold { amount: i32 },
+    // splits in two when hurt but not killed
+    Split,
+    // ranged attack along a clear line, needs time to recover
+    Breath { damage: i32, range: i32, cooldown: i32 },
+    // casts a spell at a target in sight, needs time to recover
+    Cast { spell: Spell, range: i32, cooldown: i32 },
+}
+
+// poison working on a creature
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Poison {
+    pub damage: i32,
+    pub num_turns: i32,
+}
+
+// older saves have an unused true/false flag where the poison is now
+pub fn deserialize_poison<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Poison>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Saved {
+        Poison(Option<Poison>),
+        Flag(IgnoredAny),
+    }
+    match Saved::deserialize(deserializer)? {
+        Saved::Poison(poison) => Ok(poison),
+        Saved::Flag(_) => Ok(None),
+    }
+}
+
+// check an ability from the monster data file makes sense
+pub fn check_ability(ability: Ability) -> Result<(), String> {
+    use Ability::*;
+    let valid = match ability {
+        Regenerate { amount } | StealGold { amount } => amount > 0,
+        PoisonBite { damage, num_turns } => damage > 0 && num_turns > 0,
+        FireAura { damage } => damage > 0,
+        Split => true,
+        Breath { damage, range, cooldown } => damage > 0 && range > 0 && cooldown >= 0,
+        Cast { range, cooldown, .. } => range > 0 && cooldown >= 0,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("ability {:?} needs positive values", ability))
+    }
+}
+
+// abilities of an object's monster kind or boss phase, empty for anything else
+fn abilities(object: &Object) -> &'static [Ability] {
+    if let Some(state) = &object.boss {
+        return boss_abilities(state);
+    }
+    object
+        .kind
+        .as_deref()
+        .and_then(monster_def)
+        .map_or(&[], |def| &def.abilities)
+}
+
+// check if a monster can heal others, so it knows to stay near hurt friends
+pub fn can_heal(object: &Object) -> bool {
+    abilities(object)
+        .iter()
+        .any(|ability| matches!(ability, Ability::Cast { spell: Spell::Heal, .. }))
+}
+
+/*
+ *  Ability triggers
+ */
+
+// abilities that work on their own every turn, whatever the monster is doing
+pub fn passive_abilities(monster_id: usize, game: &mut Game, objects: &mut [Object]) {
+    if objects[monster_id].cooldown > 0 {
+        objects[monster_id].cooldown -= 1;
+    }
+    for &ability in abilities(&objects[monster_id]) {
+        match ability {
+            Ability::Regenerate { amount } => objects[monster_id].heal(amount, game),
+            Ability::FireAura { damage } => fire_aura(monster_id, damage, game, objects),
+            _ => (),
+        }
+    }
+}
+
+// abilities a monster uses instead of its normal move, returns true if one was used
+pub fn active_abilities(monster_id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    if objects[monster_id].cooldown > 0 {
+        return false;
+    }
+    for &ability in abilities(&objects[monster_id]) {
+        let used = match ability {
+            Ability::Breath { damage, range, cooldown } => {
+                breath(monster_id, damage, range, game, objects).then_some(cooldown)
+            }
+            Ability::Cast { spell, range, cooldown } => {
+                monster_cast(monster_id, spell, range, game, objects).then_some(cooldown)
+            }
+            _ => None,
+        };
+        if let Some(cooldown) = used {
+            objects[monster_id].cooldown = cooldown;
+            return true;
+        }
+    }
+    false
+}
+
+// abilities that trigger when a monster's attack lands on a target that survives
+pub fn on_hit(attacker: &mut Object, target: &mut Object, game: &mut Game) {
+    for &ability in abilities(attacker) {
+        match ability {
+            Ability::PoisonBite { damage, num_turns } => {
+                if target.poisoned.is_none() {
+                    game.messages.add(format!("The {} is poisoned!", target.name), GREEN);
+                }
+                target.poisoned = Some(Poison { damage, num_turns });
+            }
+            Ability::StealGold { amount } if target.name == "player" && game.gold > 0 => {
+                let stolen = cmp::min(amount, game.gold);
+                game.gold -= stolen;
+                game.messages.add(
+                    format!("The {} steals {} gold and runs off!", attacker.name, stolen),
+                    GOLD,
+                );
+                change_ai(attacker, AiChange::Flee { num_turns: FLEE_TURNS });
+            }
+            _ => (),
+        }
+    }
+}
+
+// abilities that trigger when a monster is hurt but not killed
+pub fn on_damaged(monster: &mut Object, game: &mut Game) {
+    if !abilities(monster).contains(&Ability::Split) {
+        return;
+    }
+    let def = match monster.kind.as_deref().and_then(monster_def) {
+        Some(def) => def,
+        None => return,
+    };
+    if let Some(fighter) = monster.fighter.as_mut() {
+        // too small to split any further
+        if fighter.hp < 2 {
+            return;
+        }
+        // each half gets half of what is left, and is worth half the xp
+        let mut half = create_monster(def, monster.x, monster.y);
+        if let Some(half_fighter) = half.fighter.as_mut() {
+            half_fighter.hp = fighter.hp / 2;
+            half_fighter.xp = fighter.xp / 2;
+        }
+        fighter.hp -= fighter.hp / 2;
+        fighter.xp -= fighter.xp / 2;
+        half.ai = monster.ai.clone().or(half.ai);
+
+        game.messages.add(format!("The {} splits in two!", monster.name), LIGHT_GREEN);
+        game.spawned.push(half);
+    }
+}
+
+/*
+ *  Ability effects
+ */
+
+// burn every hostile standing next to the monster
+fn fire_aura(monster_id: usize, damage: i32, game: &mut Game, objects: &mut [Object]) {
+    for id in 0..objects.len() {
+        if id != monster_id
+            && objects[monster_id].is_hostile_to(&objects[id])
+            && objects[monster_id].distance_to(&objects[id]) < 2.0
+        {
+            game.messages.add(
+                format!("The {} is scorched by the {}'s flames!", objects[id].name, objects[monster_id].name),
+                ORANGE,
+            );
+            if let Some(xp) = objects[id].take_damage(damage, game) {
+                award_xp(monster_id, xp, game, objects);
+            }
+        }
+    }
+}
+
+// breathe on the closest hostile in range if nothing is in the way, returns true if it did
+fn breath(monster_id: usize, damage: i32, range: i32, game: &mut Game, objects: &mut [Object]) -> bool {
+    let (faction, pos) = (objects[monster_id].faction(), objects[monster_id].pos());
+    let target_id = match closest_hostile(faction, pos, objects, range as f32) {
+        Some(target_id) => target_id,
+        None => return false,
+    };
+    let (_, _, hit_id) = projectile_path(pos, objects[target_id].pos(), &game.map, objects);
+    if hit_id != Some(target_id) || rand::thread_rng().gen_range(0, 100) >= BREATH_CHANCE {
+        return false;
+    }
+
+    game.messages.add(
+        format!(
+            "The {} breathes fire at {} for {} damage!",
+            objects[monster_id].name, objects[target_id].name, damage
+        ),
+        FLAME,
+    );
+    if let Some(xp) = objects[target_id].take_damage(damage, game) {
+        award_xp(monster_id, xp, game, objects);
+    }
+    true
+}
+
+// function to hurt everything that is poisoned, called once a turn
+pub fn tick_poison(game: &mut Game, objects: &mut [Object]) {
+    for (id, object) in objects.iter_mut().enumerate() {
+        let poison = match object.poisoned {
+            Some(poison) if object.alive => poison,
+            _ => continue,
+        };
+        object.poisoned = if poison.num_turns > 1 {
+            Some(Poison {
+                num_turns: poison.num_turns - 1,
+                ..poison
+            })
+        } else {
+            if id == PLAYER {
+                game.messages.add("The poison wears off.", GREEN);
+            }
+            None
+        };
+        object.take_damage(poison.damage, game);
+    }
+}
+
+// function to put monsters created mid-turn, like split jellies, on a free tile next to where they appeared
+pub fn place_spawned(game: &mut Game, objects: &mut Vec<Object>) {
+    for mut monster in game.spawned.drain(..) {
+        let (x, y) = monster.pos();
+        let free_spot = (-1..2)
+            .flat_map(|dx| (-1..2).map(move |dy| (x + dx, y + dy)))
+            .find(|&(x, y)| !is_blocked(x, y, &game.map, objects));
+        // no room means it doesn't appear
+        if let Some((x, y)) = free_spot {
+            monster.set_pos(x, y);
+            monster.alive = true;
+            objects.push(monster);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use rand::Rng;
+use tcod::colors::*;
+
+use crate::menu::menu;
+use crate::map::{Map, Rect};
+use crate::object::{Object, Fighter, DeathCallback, Attributes, is_blocked};
+use crate::monster_ai::Ai;
+use crate::game::{Tcod, Game};
+use crate::faction::Faction;
+
+const MERCENARY_CHANCE: i32 = 25;     // percent chance a level has a mercenary for hire
+const MERCENARY_PRICE_PER_LEVEL: i32 = 20;
+const ORDERS_SCREEN_WIDTH: i32 = 40;
+const PLAYER: usize = 0;
+
+/*
+ *  Creating allies
+ */
+
+// function to create the player's starting pet
+pub fn create_pet_dog() -> Object {
+    let mut dog = Object::new(0, 0, 'd', "dog", LIGHT_SEPIA, true);
+    dog.fighter = Some(Fighter {
+        base_max_hp: 25,
+        hp: 25,
+        base_defense: 0,
+        base_power: 3,
+        base_magic: 0,
+        base_stealth: 0,
+        xp: 0,
+        on_death: DeathCallback::Monster,
+        attributes: Attributes::average(),
+    });
+    dog.ai = Some(Ai::Follow);
+    dog.faction = Faction::Player;
+    dog.alive = true;
+    dog
+}
+
+// function to maybe put a mercenary waiting to be hired in a room
+pub fn place_mercenary(room: Rect, map: &Map, objects: &mut Vec<Object>, level: u32) {
+    if rand::thread_rng().gen_range(0, 100) >= MERCENARY_CHANCE {
+        return;
+    }
+
+    let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
+    let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
+    if is_blocked(x, y, map, objects) {
+        return;
+    }
+
+    // mercenaries get tougher, and pricier, deeper in the dungeon
+    let level = level as i32;
+    let mut mercenary = Object::new(x, y, '@', "mercenary", LIGHT_BLUE, true);
+    mercenary.fighter = Some(Fighter {
+        base_max_hp: 30 + level * 5,
+        hp: 30 + level * 5,
+        base_defense: 1 + level / 3,
+        base_power: 4 + level / 2,
+        base_magic: 0,
+        base_stealth: 0,
+        xp: 0,
+        on_death: DeathCallback::Monster,
+        attributes: Attributes::average(),
+    });
+    mercenary.ai = Some(Ai::ForHire {
+        price: MERCENARY_PRICE_PER_LEVEL * level,
+    });
+    // stays out of fights until someone pays
+    mercenary.faction = Faction::Neutral;
+    mercenary.alive = true;
+    objects.push(mercenary);
+}
+
+// function to put an ally on a free tile next to the player
+pub fn place_near_player(mut ally: Object, map: &Map, objects: &mut Vec<Object>) {
+    let (player_x, player_y) = objects[PLAYER].pos();
+    for dx in -1..2 {
+        for dy in -1..2 {
+            if !is_blocked(player_x + dx, player_y + dy, map, objects) {
+                ally.set_pos(player_x + dx, player_y + dy);
+                objects.push(ally);
+                return;
+            }
+        }
+    }
+    // no room next to the player, so it is left behind
+}
+
+/*
+ *  Hiring and giving orders
+ */
+
+// function to hire a mercenary the player bumped into
+pub fn hire(mercenary_id: usize, price: i32, game: &mut Game, objects: &mut [Object]) {
+    let mercenary = &mut objects[mercenary_id];
+    if game.gold >= price {
+        game.gold -= price;
+        mercenary.faction = Faction::Player;
+        mercenary.ai = Some(Ai::Follow);
+        game.messages.add(
+            format!("You hire the {} for {} gold. It will follow you.", mercenary.name, price),
+            LIGHT_BLUE,
+        );
+    } else {
+        game.messages.add(
+            format!("The {} wants {} gold to join you.", mercenary.name, price),
+            WHITE,
+        );
+    }
+}
+
+// function to show list of allies and give one of them an order
+pub fn give_orders(tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
+    let ally_ids: Vec<usize> = objects
+        .iter()
+        .enumerate()
+        .filter(|(id, object)| *id != PLAYER && object.faction == Faction::Player && object.alive)
+        .map(|(id, _)| id)
+        .collect();
+
+    if ally_ids.is_empty() {
+        game.messages.add("You have no allies to give orders to.", WHITE);
+        return;
+    }
+
+    let names: Vec<String> = ally_ids.iter().map(|&id| objects[id].name.clone()).collect();
+    let ally_id = match menu("Choose an ally to command:\n", &names, ORDERS_SCREEN_WIDTH, &mut tcod.root) {
+        Some(index) => ally_ids[index],
+        None => return,
+    };
+
+    let orders = &["Follow me", "Guard this spot", "Attack my target"];
+    let (x, y) = objects[ally_id].pos();
+    let order = match menu("What should it do?\n", orders, ORDERS_SCREEN_WIDTH, &mut tcod.root) {
+        Some(0) => Ai::Follow,
+        Some(1) => Ai::Guard { x, y },
+        Some(2) => Ai::AttackTarget,
+        _ => return,
+    };
+
+    game.messages.add(
+        format!("The {} acknowledges your order.", objects[ally_id].name),
+        LIGHT_BLUE,
+    );
+    objects[ally_id].ai = Some(order);
+}
+
+// function to take living allies out of the object list so they can come down the stairs
+pub fn gather_allies(objects: &mut Vec<Object>) -> Vec<Object> {
+    let mut allies = vec![];
+    let mut id = objects.len();
+    while id > 1 {
+        id -= 1;
+        if objects[id].faction == Faction::Player && objects[id].alive {
+            let mut ally = objects.swap_remove(id);
+            // guard posts don't make sense on a new level
+            if let Some(Ai::Guard { .. }) = ally.ai {
+                ally.ai = Some(Ai::Follow);
+            }
+            allies.push(ally);
+        }
+    }
+    allies
+}
@@ -0,0 +1,254 @@
+use std::error::Error;
+use std::sync::OnceLock;
+use tcod::colors::*;
+use serde::{Deserialize, Serialize};
+
+use crate::object::{Object, Fighter, DeathCallback, Attributes};
+use crate::item::{Item, create_item, item_def};
+use crate::monster_ai::Ai;
+use crate::monster::read_data_file;
+use crate::faction::Faction;
+use crate::ability::{Ability, check_ability};
+use crate::game::Game;
+
+const BOSS_FILE: &str = "../data/bosses.json";
+pub const BOSS_LEVEL_INTERVAL: u32 = 5; // every 5th level is guarded by a boss
+
+// boss definitions, loaded once at startup
+static BOSS_DEFS: OnceLock<Vec<BossDef>> = OnceLock::new();
+
+// layout of the room a boss is fought in
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Arena {
+    Pillars,  // large hall broken up by pillars
+    Ring,     // hall with a solid block in the middle to circle around
+    Cross,    // two crossing halls
+    Gates,    // hall split by a wall with a few gaps
+}
+
+// a stage of a boss fight, starting once the boss is hurt badly enough
+#[derive(Clone, Debug, Deserialize)]
+pub struct Phase {
+    pub hp_percent: i32,  // starts when hp drops to this percent of max
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub power_bonus: i32,
+    #[serde(default)]
+    pub defense_bonus: i32,
+    #[serde(default)]
+    pub abilities: Vec<Ability>,
+}
+
+// unique item a boss always drops
+#[derive(Clone, Debug, Deserialize)]
+pub struct Artifact {
+    pub item: String,  // id of an item definition, it has to be equipment
+    pub name: String,
+    #[serde(default)]
+    pub power_bonus: i32,
+    #[serde(default)]
+    pub defense_bonus: i32,
+    #[serde(default)]
+    pub max_hp_bonus: i32,
+    #[serde(default)]
+    pub magic_bonus: i32,
+    #[serde(default = "Attributes::none")]
+    pub attribute_bonus: Attributes,
+}
+
+// hand-designed definition of a boss and its level, read from the boss data file
+#[derive(Clone, Debug, Deserialize)]
+pub struct BossDef {
+    pub kind: String,  // id saved bosses and the list of slain ones refer to it by
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    pub level: u32,
+    pub arena: Arena,
+    pub max_hp: i32,
+    pub defense: i32,
+    pub power: i32,
+    #[serde(default)]
+    pub magic: i32,
+    pub xp: i32,
+    pub faction: Faction,
+    pub phases: Vec<Phase>,  // in order, the first starts at 100 percent
+    pub artifact: Artifact,
+}
+
+// which boss an object is and how far into its fight it is
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BossState {
+    pub kind: String,
+    pub phase: usize,
+}
+
+/*
+ *  Loading boss definitions
+ */
+
+// function to read and check the boss data file, must be called once before any boss levels are made
+// and after the item definitions, which artifacts are checked against
+pub fn load_boss_defs() -> Result<(), Box<dyn Error>> {
+    let defs: Vec<BossDef> = read_data_file(BOSS_FILE)?;
+    validate_boss_defs(&defs).map_err(|e| format!("{}: {}", BOSS_FILE, e))?;
+    BOSS_DEFS
+        .set(defs)
+        .map_err(|_| "boss definitions loaded twice")?;
+    Ok(())
+}
+
+// check for mistakes serde can't catch, returns a description of the first one found
+fn validate_boss_defs(defs: &[BossDef]) -> Result<(), String> {
+    for (i, def) in defs.iter().enumerate() {
+        if def.kind.is_empty() {
+            return Err(format!("'{}' has an empty kind", def.name));
+        }
+        if defs[..i].iter().any(|other| other.kind == def.kind) {
+            return Err(format!("{} is defined more than once", def.kind));
+        }
+        if def.name.is_empty() {
+            return Err(format!("{} has an empty name", def.kind));
+        }
+        if def.level == 0 || !def.level.is_multiple_of(BOSS_LEVEL_INTERVAL) {
+            return Err(format!("'{}' level must be a multiple of {}", def.name, BOSS_LEVEL_INTERVAL));
+        }
+        if defs[..i].iter().any(|other| other.level == def.level) {
+            return Err(format!("'{}' shares level {} with another boss", def.name, def.level));
+        }
+        if def.max_hp <= 0 {
+            return Err(format!("'{}' must have max_hp above 0", def.name));
+        }
+        if def.xp < 0 {
+            return Err(format!("'{}' can't give negative xp", def.name));
+        }
+        if def.phases.first().is_none_or(|phase| phase.hp_percent != 100) {
+            return Err(format!("'{}' first phase must start at 100 percent", def.name));
+        }
+        if def.phases.windows(2).any(|pair| pair[1].hp_percent <= 0 || pair[0].hp_percent <= pair[1].hp_percent) {
+            return Err(format!("'{}' phases must start at falling hp percents above 0", def.name));
+        }
+        if def.phases[1..].iter().any(|phase| phase.message.is_empty()) {
+            return Err(format!("'{}' phases after the first need a message", def.name));
+        }
+        for phase in &def.phases {
+            for &ability in &phase.abilities {
+                check_ability(ability).map_err(|e| format!("'{}' {}", def.name, e))?;
+            }
+        }
+        if def.artifact.name.is_empty() {
+            return Err(format!("'{}' artifact has an empty name", def.name));
+        }
+        if item_def(&def.artifact.item).is_none_or(|item| item.equipment.is_none()) {
+            return Err(format!("'{}' artifact is a {}, which isn't defined equipment", def.name, def.artifact.item));
+        }
+    }
+    Ok(())
+}
+
+// all loaded boss definitions
+fn boss_defs() -> &'static [BossDef] {
+    BOSS_DEFS.get().expect("boss definitions not loaded")
+}
+
+/*
+ *  Boss related functions
+ */
+
+// definition for one kind of boss
+pub fn boss_def(kind: &str) -> Option<&'static BossDef> {
+    boss_defs().iter().find(|def| def.kind == kind)
+}
+
+// the boss guarding a level, if it hasn't been killed already
+pub fn boss_for_level(level: u32, slain: &[String]) -> Option<&'static BossDef> {
+    if !level.is_multiple_of(BOSS_LEVEL_INTERVAL) {
+        return None;
+    }
+    boss_defs()
+        .iter()
+        .find(|def| def.level == level && !slain.contains(&def.kind))
+}
+
+// abilities a boss uses in its current phase
+pub fn boss_abilities(state: &BossState) -> &'static [Ability] {
+    boss_def(&state.kind).map_or(&[], |def| &def.phases[state.phase].abilities)
+}
+
+// function to create a boss monster, awake and waiting in its arena
+pub fn create_boss(def: &BossDef, x: i32, y: i32) -> Object {
+    let mut boss = Object::new(x, y, def.glyph, &def.name, def.color, true);
+    boss.fighter = Some(Fighter {
+        base_max_hp: def.max_hp,
+        hp: def.max_hp,
+        base_defense: def.defense,
+        base_power: def.power,
+        base_magic: def.magic,
+        base_stealth: 0,
+        xp: def.xp,
+        on_death: DeathCallback::Monster,
+        attributes: Attributes::average(),
+    });
+    boss.ai = Some(Ai::Basic);
+    boss.faction = def.faction;
+    boss.boss = Some(BossState { kind: def.kind.clone(), phase: 0 });
+    boss.alive = true;
+    boss
+}
+
+// function to move a hurt boss on to its next phase once it drops below the threshold
+pub fn check_boss_phase(boss: &mut Object, game: &mut Game) {
+    let (state, fighter) = match (boss.boss.as_mut(), boss.fighter.as_mut()) {
+        (Some(state), Some(fighter)) => (state, fighter),
+        _ => return,
+    };
+    let def = match boss_def(&state.kind) {
+        Some(def) => def,
+        None => return,
+    };
+    let max_hp = fighter.base_max_hp;
+
+    // a big hit can skip straight past a phase, so apply each one in turn
+    while state.phase + 1 < def.phases.len()
+        && fighter.hp * 100 <= def.phases[state.phase + 1].hp_percent * max_hp
+    {
+        state.phase += 1;
+        let phase = &def.phases[state.phase];
+        fighter.base_power += phase.power_bonus;
+        fighter.base_defense += phase.defense_bonus;
+        game.messages.add(&phase.message, ORANGE);
+    }
+}
+
+// function for when a boss dies, it drops its artifact and the stairs unseal
+pub fn boss_death(boss: &Object, game: &mut Game) {
+    let def = match boss.boss.as_ref().and_then(|state| boss_def(&state.kind)) {
+        Some(def) => def,
+        None => return,
+    };
+    game.slain_bosses.push(def.kind.clone());
+
+    let mut artifact = create_item(Item::Kind(def.artifact.item.clone()), boss.x, boss.y);
+    artifact.name = def.artifact.name.clone();
+    artifact.color = GOLD;
+    artifact.always_visible = true;
+    if let Some(equipment) = artifact.equipment.as_mut() {
+        equipment.power_bonus += def.artifact.power_bonus;
+        equipment.defense_bonus += def.artifact.defense_bonus;
+        equipment.max_hp_bonus += def.artifact.max_hp_bonus;
+        equipment.magic_bonus += def.artifact.magic_bonus;
+        equipment.attribute_bonus = equipment.attribute_bonus + def.artifact.attribute_bonus;
+    }
+    game.loot.push(artifact);
+
+    game.messages.add(
+        format!("With {} gone, the seal on the stairs breaks!", def.name),
+        LIGHT_VIOLET,
+    );
+}
+
+// check if a living boss is still keeping the stairs sealed
+pub fn stairs_sealed(objects: &[Object]) -> bool {
+    objects.iter().any(|object| object.boss.is_some() && object.alive)
+}
@@ -0,0 +1,217 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::map::Map;
+use crate::object::{Object, is_blocked};
+use crate::pathfinding::{STRAIGHT_COST, DIAGONAL_COST, OCCUPIED_COST};
+
+pub const UNREACHABLE: i32 = i32::MAX;
+const FLEE_FACTOR: f32 = -1.2;  // how strongly a flee map prefers getting far away over the shortest escape
+
+// cost of walking to every tile from the closest of a set of goals
+pub struct DijkstraMap {
+    height: i32,
+    distances: Vec<i32>,
+}
+
+impl DijkstraMap {
+    // build a map from goals that can each start at their own value, like a more or less wanted item
+    // cost gives the extra cost of stepping onto a tile, or None if it can't be walked on
+    pub fn new<F>(goals: &[((i32, i32), i32)], map: &Map, cost: F) -> Self
+    where
+        F: Fn(i32, i32) -> Option<i32>,
+    {
+        let height = map[0].len() as i32;
+        let mut dijkstra = DijkstraMap {
+            height,
+            distances: vec![UNREACHABLE; map.len() * height as usize],
+        };
+        for &((x, y), value) in goals {
+            let index = dijkstra.index(x, y);
+            dijkstra.distances[index] = value;
+        }
+        dijkstra.scan(map, cost);
+        dijkstra
+    }
+
+    // map towards goals for something walking, going around other creatures where it can
+    pub fn walking(goals: &[(i32, i32)], map: &Map, objects: &[Object]) -> Self {
+        let goals: Vec<_> = goals.iter().map(|&goal| (goal, 0)).collect();
+        DijkstraMap::new(&goals, map, walking_cost(map, objects))
+    }
+
+    // turn a map towards something into one for running away from it, downhill leads away
+    // and towards open space, instead of into the nearest dead end
+    pub fn flee(&self, map: &Map, objects: &[Object]) -> Self {
+        let mut flee = DijkstraMap {
+            height: self.height,
+            distances: self
+                .distances
+                .iter()
+                .map(|&distance| {
+                    if distance == UNREACHABLE {
+                        UNREACHABLE
+                    } else {
+                        (distance as f32 * FLEE_FACTOR) as i32
+                    }
+                })
+                .collect(),
+        };
+        flee.scan(map, walking_cost(map, objects));
+        flee
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (x * self.height + y) as usize
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && y < self.height && self.index(x, y) < self.distances.len()
+    }
+
+    // cost from a tile to the closest goal
+    pub fn distance(&self, x: i32, y: i32) -> i32 {
+        if self.in_bounds(x, y) {
+            self.distances[self.index(x, y)]
+        } else {
+            UNREACHABLE
+        }
+    }
+
+    // the neighbouring tile closest to a goal, if any is closer than where it is now
+    pub fn downhill(&self, x: i32, y: i32, map: &Map, objects: &[Object]) -> Option<(i32, i32)> {
+        let mut best = None;
+        let mut best_distance = self.distance(x, y);
+        for dx in -1..2 {
+            for dy in -1..2 {
+                let (next_x, next_y) = (x + dx, y + dy);
+                let distance = self.distance(next_x, next_y);
+                if distance < best_distance && !is_blocked(next_x, next_y, map, objects) {
+                    best = Some((next_x, next_y));
+                    best_distance = distance;
+                }
+            }
+        }
+        best
+    }
+
+    // spread the values out from every tile that has one until nothing gets cheaper
+    fn scan<F>(&mut self, map: &Map, cost: F)
+    where
+        F: Fn(i32, i32) -> Option<i32>,
+    {
+        let mut open: BinaryHeap<_> = (0..self.distances.len())
+            .filter(|&index| self.distances[index] != UNREACHABLE)
+            .map(|index| Reverse((self.distances[index], index)))
+            .collect();
+
+        while let Some(Reverse((distance, index))) = open.pop() {
+            // already reached more cheaply since this was queued
+            if distance > self.distances[index] {
+                continue;
+            }
+            let (x, y) = (index as i32 / self.height, index as i32 % self.height);
+            for dx in -1..2 {
+                for dy in -1..2 {
+                    let (next_x, next_y) = (x + dx, y + dy);
+                    if (dx, dy) == (0, 0) || !self.in_bounds(next_x, next_y) {
+                        continue;
+                    }
+                    let extra = match cost(next_x, next_y) {
+                        Some(extra) if !map[next_x as usize][next_y as usize].blocked => extra,
+                        _ => continue,
+                    };
+                    let step = if dx != 0 && dy != 0 { DIAGONAL_COST } else { STRAIGHT_COST };
+                    let next_distance = distance + step + extra;
+                    let next_index = self.index(next_x, next_y);
+                    if next_distance < self.distances[next_index] {
+                        self.distances[next_index] = next_distance;
+                        open.push(Reverse((next_distance, next_index)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// walking cost where blocking objects are costly to get past but not walls
+fn walking_cost(map: &Map, objects: &[Object]) -> impl Fn(i32, i32) -> Option<i32> {
+    let height = map[0].len() as i32;
+    let mut occupied = vec![false; map.len() * height as usize];
+    for object in objects.iter().filter(|object| object.blocks) {
+        occupied[(object.x * height + object.y) as usize] = true;
+    }
+    move |x, y| Some(if occupied[(x * height + y) as usize] { OCCUPIED_COST } else { 0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Tile;
+
+    fn open_map(width: usize, height: usize) -> Map {
+        vec![vec![Tile::empty(); height]; width]
+    }
+
+    #[test]
+    fn distance_is_to_closest_goal() {
+        let map = open_map(9, 3);
+        let dijkstra = DijkstraMap::walking(&[(0, 1), (8, 1)], &map, &[]);
+
+        assert_eq!(dijkstra.distance(0, 1), 0);
+        assert_eq!(dijkstra.distance(8, 1), 0);
+        assert_eq!(dijkstra.distance(2, 1), 2 * STRAIGHT_COST);
+        assert_eq!(dijkstra.distance(6, 1), 2 * STRAIGHT_COST);
+        assert_eq!(dijkstra.distance(4, 1), 4 * STRAIGHT_COST);
+        assert_eq!(dijkstra.distance(9, 1), UNREACHABLE);
+    }
+
+    #[test]
+    fn downhill_heads_for_nearer_goal() {
+        let map = open_map(9, 3);
+        let dijkstra = DijkstraMap::walking(&[(0, 1), (8, 1)], &map, &[]);
+
+        assert_eq!(dijkstra.downhill(3, 1, &map, &[]), Some((2, 1)));
+        assert_eq!(dijkstra.downhill(5, 1, &map, &[]), Some((6, 1)));
+        assert_eq!(dijkstra.downhill(0, 1, &map, &[]), None);
+    }
+
+    #[test]
+    fn goal_values_weigh_goals() {
+        // the right goal is wanted less, so only tiles right next to it lead there
+        let map = open_map(9, 1);
+        let dijkstra = DijkstraMap::new(&[((0, 0), 0), ((8, 0), 3 * STRAIGHT_COST)], &map, |_, _| Some(0));
+
+        assert_eq!(dijkstra.distance(5, 0), 5 * STRAIGHT_COST);
+        assert_eq!(dijkstra.distance(6, 0), 5 * STRAIGHT_COST);
+        assert_eq!(dijkstra.distance(7, 0), 4 * STRAIGHT_COST);
+    }
+
+    #[test]
+    fn walls_are_unreachable() {
+        let mut map = open_map(5, 5);
+        map[2] = vec![Tile::wall(); 5];
+        let dijkstra = DijkstraMap::walking(&[(0, 2)], &map, &[]);
+
+        assert_eq!(dijkstra.distance(2, 2), UNREACHABLE);
+        assert_eq!(dijkstra.distance(4, 2), UNREACHABLE);
+        assert_eq!(dijkstra.downhill(4, 2, &map, &[]), None);
+    }
+
+    #[test]
+    fn flee_downhill_moves_away_from_goal() {
+        let map = open_map(11, 11);
+        let towards = DijkstraMap::walking(&[(5, 5)], &map, &[]);
+        let flee = towards.flee(&map, &[]);
+
+        for start in [(6, 5), (4, 4), (5, 8), (2, 7)] {
+            let step = flee.downhill(start.0, start.1, &map, &[]).expect("somewhere to run");
+            assert!(
+                towards.distance(step.0, step.1) > towards.distance(start.0, start.1),
+                "{:?} to {:?} isn't away from the goal",
+                start,
+                step
+            );
+        }
+    }
+}
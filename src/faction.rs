@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+// which side a creature fights for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Faction {
+    Player,   // the player and their allies
+    Orcs,
+    Bandits,
+    Undead,
+    Demons,
+    #[default]
+    Neutral,  // fights nobody, like a mercenary waiting to be hired
+    Feral,    // fights everybody, like a confused monster
+}
+
+impl Faction {
+    // check if two factions will fight each other
+    pub fn is_hostile_to(self, other: Faction) -> bool {
+        use Faction::*;
+        match (self, other) {
+            // nobody fights their own side, and neutrals stay out of it
+            (a, b) if a == b => false,
+            (Neutral, _) | (_, Neutral) => false,
+            (Feral, _) | (_, Feral) => true,
+            // everyone is out to get the player
+            (Player, _) | (_, Player) => true,
+            // the dead hate the living, demons aren't living
+            (Undead, Demons) | (Demons, Undead) => false,
+            (Undead, _) | (_, Undead) => true,
+            (Orcs, Bandits) | (Bandits, Orcs) => true,
+            _ => false,
+        }
+    }
+}
+
+// a temporary change of side, from charm or confusion
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TempFaction {
+    pub faction: Faction,
+    pub num_turns: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Faction::*;
+
+    const ALL: [Faction; 7] = [Player, Orcs, Bandits, Undead, Demons, Neutral, Feral];
+
+    #[test]
+    fn hostility_goes_both_ways() {
+        for a in ALL {
+            for b in ALL {
+                assert_eq!(a.is_hostile_to(b), b.is_hostile_to(a), "{:?} and {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn sides() {
+        for faction in ALL {
+            assert!(!faction.is_hostile_to(faction));
+            assert!(!faction.is_hostile_to(Neutral));
+        }
+        for faction in [Orcs, Bandits, Undead, Demons, Feral] {
+            assert!(Player.is_hostile_to(faction));
+        }
+        assert!(Feral.is_hostile_to(Orcs));
+        assert!(Orcs.is_hostile_to(Bandits));
+        assert!(Undead.is_hostile_to(Orcs));
+        assert!(!Undead.is_hostile_to(Demons));
+        assert!(!Demons.is_hostile_to(Orcs));
+    }
+}
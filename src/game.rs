@@ -9,12 +9,22 @@ use tcod::input::{self, Event, Key, Mouse};
 use serde::{Deserialize, Serialize};
 
 use crate::message::Messages;
-use crate::map::{Map, make_map};
-use crate::object::{Object, PlayerAction, Fighter, DeathCallback, level_up};
+use crate::map::{Map, make_map, make_boss_map};
+use crate::object::{Object, PlayerAction, Fighter, DeathCallback, Attributes, level_up};
 use crate::item::*;
 use crate::monster_ai::{Ai, ai_take_turn};
 use crate::menu::{main_menu};
 use crate::graphics::{render_all, handle_keys};
+use crate::noise::Noise;
+use crate::perk::Perk;
+use crate::skill::Skills;
+use crate::loot::place_loot;
+use crate::ally::{create_pet_dog, place_near_player, gather_allies};
+use crate::faction::Faction;
+use crate::ability::{tick_poison, place_spawned};
+use crate::boss::boss_for_level;
+use crate::monster::{wandering_monsters, monster_defs};
+use crate::spawner::tick_spawners;
 
 const MAP_WIDTH: i32 = 100;
 const MAP_HEIGHT: i32 = 53;
@@ -38,6 +48,25 @@ pub struct Game {
     pub messages: Messages,
     pub inventory: Vec<Object>,
     pub dungeon_level: u32,
+    // fields below are missing from older saves, which load with these defaults
+    #[serde(default)]
+    pub noise: Vec<Noise>,
+    #[serde(default)]
+    pub perks: Vec<Perk>,
+    #[serde(default = "Skills::new")]
+    pub skills: Skills,
+    #[serde(default)]
+    pub gold: i32,
+    #[serde(default)]
+    pub loot: Vec<Object>,  // items dropped this turn, waiting to be placed in objects
+    #[serde(default)]
+    pub player_target: Option<(i32, i32)>,  // where the player last attacked, for allies to follow
+    #[serde(default)]
+    pub spawned: Vec<Object>,  // monsters created this turn, waiting to be placed in objects
+    #[serde(default)]
+    pub slain_bosses: Vec<String>,
+    #[serde(default)]
+    pub level_turns: u32,  // turns spent on this level, wandering monsters come more often the longer it gets
 }
 
 
@@ -51,9 +80,12 @@ pub fn new_game(tcod: &mut Tcod) -> (Game, Vec<Object>) {
         base_defense: 1,
         base_power: 2,
         base_magic: 0,
+        base_stealth: 2,
         xp: 0,
         on_death: DeathCallback::Player,
+        attributes: Attributes::average(),
     });
+    player.faction = Faction::Player;
 
     let mut objects = vec![player];
     
@@ -63,20 +95,26 @@ pub fn new_game(tcod: &mut Tcod) -> (Game, Vec<Object>) {
         messages: Messages::new(),
         inventory: vec![],
         dungeon_level: 1,
+        noise: vec![],
+        perks: vec![],
+        skills: Skills::new(),
+        gold: 0,
+        loot: vec![],
+        player_target: None,
+        spawned: vec![],
+        slain_bosses: vec![],
+        level_turns: 0,
     };
 
     // initial equipment
-    let mut dagger = Object::new(0, 0, '-', "dagger", CYAN, false);
-    dagger.item = Some(Item::Sword);
-    dagger.equipment = Some(Equipment {
-        equipped: true,
-        slot: Slot::LeftHand,
-        max_hp_bonus: 0,
-        power_bonus: 2,
-        defense_bonus: 0,
-        magic_bonus: 0,
-    });
-    game.inventory.push(dagger);
+    let mut weapon = create_item(Item::Kind(STARTING_WEAPON.into()), 0, 0);
+    if let Some(equipment) = weapon.equipment.as_mut() {
+        equipment.equipped = true;
+    }
+    game.inventory.push(weapon);
+
+    // starting pet
+    place_near_player(create_pet_dog(), &game.map, &mut objects);
     
     initialize_fov(tcod, &game.map);
 
@@ -108,8 +146,23 @@ pub fn load_game() -> Result<(Game, Vec<Object>), Box<dyn Error>> {
     let mut json_save_state = String::new();
     let mut file = File::open("savegame")?;
     file.read_to_string(&mut json_save_state)?;
-    let result = serde_json::from_str::<(Game, Vec<Object>)>(&json_save_state)?;
-    Ok(result)
+    let (game, mut objects) = serde_json::from_str::<(Game, Vec<Object>)>(&json_save_state)?;
+    // saves from before factions have the player on the neutral side
+    if objects[PLAYER].faction != Faction::Player {
+        upgrade_old_save(&mut objects);
+    }
+    Ok((game, objects))
+}
+
+// function to give objects from an older save the kind and side they would have now
+fn upgrade_old_save(objects: &mut [Object]) {
+    objects[PLAYER].faction = Faction::Player;
+    for object in objects.iter_mut().filter(|object| object.fighter.is_some() && object.ai.is_some()) {
+        if let Some(def) = monster_defs().iter().find(|def| def.name == object.name) {
+            object.kind = Some(def.kind.clone());
+            object.faction = def.faction;
+        }
+    }
 }
 
 // function to handle initializing an FOV for new or loaded game
@@ -164,6 +217,9 @@ pub fn play_game(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
             save_game(game, objects).unwrap();
             break;
         }
+        // anything killed by the player drops its loot
+        place_loot(game, objects);
+        place_spawned(game, objects);
 
         // let monsters take their turn
         if objects[PLAYER].alive && player_action != PlayerAction::DidntTakeTurn {
@@ -173,6 +229,14 @@ pub fn play_game(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
                     ai_take_turn(id, tcod, game, objects);
                 }
             }
+            tick_poison(game, objects);
+            monsters_pick_up(tcod, game, objects);
+            wandering_monsters(tcod, game, objects);
+            tick_spawners(tcod, game, objects);
+            // noise only lasts for the turn it was made
+            game.noise.clear();
+            place_loot(game, objects);
+            place_spawned(game, objects);
         }
     }
 }
@@ -189,11 +253,26 @@ pub fn next_level(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
     
     // add level and make new map and fov map
     game.dungeon_level += 1;
+    game.level_turns = 0;
     // remove all objects except player 
     // note: player must be first element 
     assert_eq!(&objects[PLAYER] as *const _, &objects[0] as *const _); // compare ptrs to object
+    // allies come along
+    let allies = gather_allies(objects);
     objects.truncate(1);
    
-    game.map = make_map(objects, game.dungeon_level);
+    game.map = match boss_for_level(game.dungeon_level, &game.slain_bosses) {
+        Some(boss) => {
+            game.messages.add(format!("You sense the presence of {}...", boss.name), LIGHT_VIOLET);
+            make_boss_map(boss, objects)
+        }
+        None => make_map(objects, game.dungeon_level),
+    };
+    if !allies.is_empty() {
+        game.messages.add("Your allies follow you down the stairs.", LIGHT_BLUE);
+    }
+    for ally in allies {
+        place_near_player(ally, &game.map, objects);
+    }
     initialize_fov(tcod, &game.map);
 }
@@ -5,9 +5,13 @@ use tcod::input::{self, Event, Key, Mouse};
 use tcod::map::{FovAlgorithm, Map as FovMap}; 
 
 use crate::game::{Tcod, Game, next_level};
-use crate::object::{Object, PlayerAction, player_move_or_attack};
+use crate::object::{Object, PlayerAction, player_move_or_attack, modifier};
 use crate::menu::{inventory_menu, msgbox};
-use crate::item::{pick_item_up, use_item, drop_item};
+use crate::item::{pick_item_up, use_item, drop_item, throw_item};
+use crate::perk::perk_name;
+use crate::skill::ALL_SKILLS;
+use crate::ally::give_orders;
+use crate::boss::stairs_sealed;
 
 const PLAYER: usize = 0;
 const LEVEL_UP_BASE: i32 = 200; // need 200 xp for first level up
@@ -132,6 +136,15 @@ pub fn render_all(tcod: &mut Tcod, game: &mut Game, objects: &[Object], fov_reco
         format!("Dungeon Level: {}", game.dungeon_level),
     );
 
+    // display gold carried
+    tcod.panel.print_ex(
+        1,
+        4,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        format!("Gold: {}", game.gold),
+    );
+
     // display names of objects under the mouse
     tcod.panel.set_default_foreground(LIGHT_GREY);
     tcod.panel.print_ex(
@@ -292,6 +305,24 @@ pub fn handle_keys(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>)
             }
             DidntTakeTurn
         }
+        (Key { code: Text, ..}, "t", true) => {
+            // show inventory, if item selected, pick a target and throw it
+            let inventory_index = inventory_menu(
+                &game.inventory,
+                "Press the key next to an item you want to throw, or any other to cancel.\n",
+                &mut tcod.root,
+            );
+
+            match inventory_index {
+                Some(inventory_index) if throw_item(inventory_index, tcod, game, objects) => TookTurn,
+                _ => DidntTakeTurn,
+            }
+        }
+        (Key { code: Text, ..}, "o", true) => {
+            // give orders to allies
+            give_orders(tcod, game, objects);
+            DidntTakeTurn
+        }
         (Key { code: Text, ..}, "i", true) => {
             // show the inventory
             let inventory_index = inventory_menu(
@@ -311,6 +342,13 @@ pub fn handle_keys(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>)
             let level_up_xp = LEVEL_UP_BASE + level * LEVEL_UP_FACTOR;
             
             if let Some(fighter) = player.fighter.as_ref() {
+                let attributes = player.attributes(game);
+                let skills = ALL_SKILLS
+                    .iter()
+                    .map(|&skill| format!("{} {}", skill, game.skills.get(skill).level))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let perks = game.perks.iter().map(|&perk| perk_name(perk)).collect::<Vec<_>>().join(", ");
                 let msg = format!(
                         "Character Information
 
@@ -318,10 +356,34 @@ pub fn handle_keys(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>)
             Experience: {}
             Next Level: {}
 
+            Strength: {} ({:+})
+            Dexterity: {} ({:+})
+            Constitution: {} ({:+})
+            Intelligence: {} ({:+})
+            Wisdom: {} ({:+})
+
             Maximum HP: {}
             Attack: {}
-            Defense: {}",
-                    level, fighter.xp, level_up_xp, player.max_hp(game), player.power(game), player.defense(game),
+            Defense: {}
+            Evasion: {}%
+            Spell Power: {}
+            Stealth: {}
+            Carry Capacity: {}
+
+            Perks: {}
+
+            Skills: {}",
+                    level, fighter.xp, level_up_xp,
+                    attributes.strength, modifier(attributes.strength),
+                    attributes.dexterity, modifier(attributes.dexterity),
+                    attributes.constitution, modifier(attributes.constitution),
+                    attributes.intelligence, modifier(attributes.intelligence),
+                    attributes.wisdom, modifier(attributes.wisdom),
+                    player.max_hp(game), player.power(game), player.defense(game),
+                    player.evasion(game), player.spell_power(game), player.stealth(game),
+                    player.carry_capacity(game),
+                    perks,
+                    skills,
                 );
 
                 msgbox(&msg, CHARACTER_SCREEN_WIDTH, &mut tcod.root);
@@ -334,7 +396,9 @@ pub fn handle_keys(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>)
             let player_on_stairs = objects
                 .iter()
                 .any(|object| object.pos() == objects[PLAYER].pos() && object.name == "stairs");
-            if player_on_stairs {
+            if player_on_stairs && stairs_sealed(objects) {
+                game.messages.add("The stairs are sealed by a powerful magic!", LIGHT_VIOLET);
+            } else if player_on_stairs {
                 next_level(tcod, game, objects);
             }
             DidntTakeTurn
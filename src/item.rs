@@ -1,32 +1,81 @@
 
+use std::error::Error;
+use std::sync::OnceLock;
+use rand::Rng;
+use rand::distributions::Weighted;
 use tcod::colors::*;
 use serde::{Deserialize, Serialize};
 
-use crate::object::Object;
+use crate::object::{Object, Attributes, Transition, projectile_path, award_xp, from_dungeon_level};
 use crate::game::{Tcod, Game};
-use crate::magic::{cast_heal, cast_confuse, cast_fireball, cast_lightning};
+use crate::monster::{monster_def, read_data_file, check_transitions};
+use crate::magic::{Spell, cast_heal, cast_confuse, cast_fireball, cast_lightning, cast_charm, cast_fear, cast_paralyze, shatter_potion, heal_target, monster_cast};
+use crate::graphics::target_tile;
+use crate::noise::{make_noise, THROW_NOISE};
+use crate::perk::Perk;
+use crate::skill::{Skill, train};
 
+const ITEM_FILE: &str = "../data/items.json";
+pub const STARTING_WEAPON: &str = "Dagger";  // the player starts with it equipped
+pub const ARENA_POTION: &str = "Heal";       // healing potions left at the entrance to a boss arena
 const MAX_INVENTORY_SIZE: usize = 26;
+const THROW_RANGE: i32 = 8;
+const SPELL_EFFICIENCY_CHANCE: i32 = 30; // percent chance to keep a scroll
+const MAX_MONSTER_INVENTORY: usize = 4;
+const MONSTER_SCROLL_RANGE: i32 = 6;
+const MONSTER_SCROLL_CHANCE: i32 = 40; // percent chance to read a scroll when there is a target for it
+const MONSTER_HEAL_PERCENT: i32 = 35;  // monsters drink a potion below this percent of max hp
 const PLAYER: usize = 0;
 
+// item definitions, loaded once at startup
+static ITEM_DEFS: OnceLock<Vec<ItemDef>> = OnceLock::new();
+
 // item related properties and methods 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Item {
+    Gold(i32),     // amount of coins in the pile
+    #[serde(untagged)]
+    Kind(String),  // id of the item's definition, saved as just the id
+}
+
+impl Item {
+    // id of the item's definition, gold has none
+    pub fn kind(&self) -> Option<&str> {
+        match self {
+            Item::Gold(_) => None,
+            Item::Kind(kind) => Some(kind),
+        }
+    }
+}
+
+// what happens when an item is used, equipment without an effect is put on or taken off instead
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum UseEffect {
     Heal,
     Lightning,
     Confuse,
     Fireball,
-    Sword,
-    //Bow,
-    //Wand,
-    Shield,
-    //Helmet,
-    //ChestPiece,
-    //Legs,
-    //Boots,
-    //Gloves,
-    //Cape,
-    //Ring,
+    Charm,
+    Fear,
+    Paralyze,
+}
+
+// everything needed to create a kind of item, read from the item data file
+#[derive(Clone, Debug, Deserialize)]
+pub struct ItemDef {
+    pub kind: String,                  // id other data files and saved items refer to it by
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    #[serde(default)]
+    pub effect: Option<UseEffect>,
+    #[serde(default)]
+    pub equipment: Option<Equipment>,
+    #[serde(default)]
+    pub skill: Option<Skill>,          // skill trained by fighting with it
+    #[serde(default)]
+    pub throw_damage: Option<i32>,     // only throwing weapons do damage when thrown
+    pub spawn_chance: Vec<Transition>, // weight in the item table by dungeon level
 }
 
 // use result for items 
@@ -40,11 +89,22 @@ pub enum UseResult {
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Equipment {
     pub slot: Slot,
+    #[serde(default)]
     pub equipped: bool,
+    #[serde(default)]
     pub max_hp_bonus: i32,
+    #[serde(default)]
     pub power_bonus: i32,
+    #[serde(default)]
     pub defense_bonus: i32,
+    #[serde(default)]
     pub magic_bonus: i32,
+    #[serde(default)]
+    pub weight: i32,  // heavier gear makes it harder to sneak
+    #[serde(default = "Attributes::none")]
+    pub attribute_bonus: Attributes,
+    #[serde(default = "Attributes::none")]
+    pub required: Attributes,  // minimum attributes needed to equip
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -81,18 +141,128 @@ impl std::fmt::Display for Slot {
 }
 
 
+/*
+ *  Loading item definitions
+ */
+
+// function to read and check the item data file, must be called once before any items are made
+pub fn load_item_defs() -> Result<(), Box<dyn Error>> {
+    let defs: Vec<ItemDef> = read_data_file(ITEM_FILE)?;
+    validate_item_defs(&defs).map_err(|e| format!("{}: {}", ITEM_FILE, e))?;
+    ITEM_DEFS
+        .set(defs)
+        .map_err(|_| "item definitions loaded twice")?;
+    Ok(())
+}
+
+// check for mistakes serde can't catch, returns a description of the first one found
+fn validate_item_defs(defs: &[ItemDef]) -> Result<(), String> {
+    if defs.is_empty() {
+        return Err("no items defined".into());
+    }
+    for (i, def) in defs.iter().enumerate() {
+        if def.kind.is_empty() {
+            return Err(format!("'{}' has an empty kind", def.name));
+        }
+        if def.kind == "Gold" {
+            return Err("gold piles are made by the game, not defined".into());
+        }
+        if defs[..i].iter().any(|other| other.kind == def.kind) {
+            return Err(format!("{} is defined more than once", def.kind));
+        }
+        if def.name.is_empty() {
+            return Err(format!("{} has an empty name", def.kind));
+        }
+        if def.effect.is_some() && def.equipment.is_some() {
+            return Err(format!("'{}' can't have both an effect and equipment", def.name));
+        }
+        if def.equipment.is_some_and(|equipment| equipment.equipped || equipment.weight < 0) {
+            return Err(format!("'{}' equipment can't start equipped or weigh less than nothing", def.name));
+        }
+        if def.throw_damage.is_some_and(|damage| damage <= 0) {
+            return Err(format!("'{}' must have throw_damage above 0", def.name));
+        }
+        check_transitions(&def.spawn_chance).map_err(|e| format!("'{}' spawn_chance {}", def.name, e))?;
+    }
+    // items the game hands out itself
+    let find = |kind: &str| defs.iter().find(|def| def.kind == kind);
+    if find(STARTING_WEAPON).is_none_or(|def| def.equipment.is_none()) {
+        return Err(format!("the starting weapon {} must be defined as equipment", STARTING_WEAPON));
+    }
+    if find(ARENA_POTION).is_none_or(|def| def.effect != Some(UseEffect::Heal)) {
+        return Err(format!("the arena potion {} must be defined with the Heal effect", ARENA_POTION));
+    }
+    Ok(())
+}
+
+// all loaded item definitions
+pub fn item_defs() -> &'static [ItemDef] {
+    ITEM_DEFS.get().expect("item definitions not loaded")
+}
+
+// definition for one kind of item
+pub fn item_def(kind: &str) -> Option<&'static ItemDef> {
+    item_defs().iter().find(|def| def.kind == kind)
+}
+
+// item table for a dungeon level, every defined item weighted by how deep it is
+pub fn item_table(level: u32) -> Vec<Weighted<Item>> {
+    item_defs()
+        .iter()
+        .map(|def| Weighted {
+            weight: from_dungeon_level(&def.spawn_chance, level),
+            item: Item::Kind(def.kind.clone()),
+        })
+        .filter(|chance| chance.weight > 0)
+        .collect()
+}
+
 /*
  * Item and equipment related functions 
  */ 
 
+// function to create the object for an item type at x/y
+pub fn create_item(item: Item, x: i32, y: i32) -> Object {
+    // gold piles are named after how many coins are in them
+    let kind = match &item {
+        Item::Gold(amount) => {
+            let mut object = Object::new(x, y, '$', &format!("{} gold coins", amount), GOLD, false);
+            object.item = Some(item);
+            return object;
+        }
+        Item::Kind(kind) => kind,
+    };
+
+    let def = item_def(kind).unwrap_or_else(|| panic!("{} has no item definition", kind));
+    let mut object = Object::new(x, y, def.glyph, &def.name, def.color, false);
+    object.item = Some(item);
+    object.equipment = def.equipment;
+    object
+}
+
 // function for player to pick up item 
 pub fn pick_item_up(object_id: usize, game: &mut Game, objects: &mut Vec<Object>) {
+    // gold goes straight into the purse instead of the inventory
+    if let Some(Item::Gold(amount)) = objects[object_id].item {
+        objects.swap_remove(object_id);
+        game.gold += amount;
+        game.messages.add(format!("You picked up {} gold coins.", amount), GOLD);
+        return;
+    }
+
+    let weight = objects[object_id].equipment.map_or(0, |e| e.weight);
     // if reached max inventory size
     if game.inventory.len() >= MAX_INVENTORY_SIZE {
         game.messages.add(
             format!("Your inventory is full! cannot pick up {}!", objects[object_id].name),
             RED,
         );
+    } else if inventory_weight(&game.inventory) + weight > objects[PLAYER].carry_capacity(game) {
+        // too heavy for the player's strength
+        game.messages.add(
+            format!("You are carrying too much! cannot pick up {}!", objects[object_id].name),
+            RED,
+        );
     } else {
         // take the object out of list and place in item 
         let item = objects.swap_remove(object_id);
@@ -101,12 +271,14 @@ pub fn pick_item_up(object_id: usize, game: &mut Game, objects: &mut Vec<Object>
             GREEN,
         );
         let index = game.inventory.len();
-        let slot = item.equipment.map(|e| e.slot);
+        let equipment = item.equipment;
         game.inventory.push(item);
         
         // if slot is empty for equipment type, then auto equip
-        if let Some(slot) = slot {
-            if get_equipped_in_slot(slot, &game.inventory).is_none() {
+        if let Some(equipment) = equipment {
+            if get_equipped_in_slot(equipment.slot, &game.inventory).is_none()
+                && meets_requirements(&equipment, &objects[PLAYER], game)
+            {
                 game.inventory[index].equip(&mut game.messages);
             }
         }
@@ -116,69 +288,273 @@ pub fn pick_item_up(object_id: usize, game: &mut Game, objects: &mut Vec<Object>
 
 // function to drop item from inventory to x/y of player
 pub fn drop_item(inventory_id: usize, game: &mut Game, objects: &mut Vec<Object>) {
+    let (x, y) = objects[PLAYER].pos();
+    let name = place_item(inventory_id, x, y, game, objects);
+    game.messages.add(format!("You dropped a {}.", name), YELLOW);
+}
+
+// function to take item out of inventory and put it on the map at x/y -- returns item name
+fn place_item(inventory_id: usize, x: i32, y: i32, game: &mut Game, objects: &mut Vec<Object>) -> String {
     let mut item = game.inventory.remove(inventory_id);
     // unequip item if it is equipped
     if item.equipment.is_some() {
         item.unequip(&mut game.messages);
     }
 
-    item.set_pos(objects[PLAYER].x, objects[PLAYER].y);
+    item.set_pos(x, y);
 
-    game.messages.add(format!("You dropped a {}.", item.name), YELLOW);
+    let name = item.name.clone();
     // item needs to be in list again to draw it
     objects.push(item);
+    name
+}
+
+// function to throw item from inventory at a target tile -- returns true if item was thrown
+pub fn throw_item(inventory_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) -> bool {
+    game.messages.add("Left click a tile to throw at, or right click to cancel.", LIGHT_CYAN);
+
+    let target = match target_tile(tcod, game, objects, Some(THROW_RANGE as f32)) {
+        Some(tile_pos) => tile_pos,
+        None => {
+            game.messages.add("Cancelled", WHITE);
+            return false;
+        }
+    };
+
+    // follow line of fire until it hits a wall or a fighter
+    let (x, y, hit_id) = projectile_path(objects[PLAYER].pos(), target, &game.map, objects);
+    let item = game.inventory[inventory_id].item.clone();
+
+    // potions break on impact and are used up
+    if let Some(potion) = item.as_ref().and_then(use_effect).filter(|&effect| is_potion(effect)) {
+        let potion_object = game.inventory.remove(inventory_id);
+        game.messages.add(format!("The {} shatters!", potion_object.name), LIGHT_CYAN);
+        shatter_potion(potion, x, y, game, objects);
+        return true;
+    }
+
+    let name = place_item(inventory_id, x, y, game, objects);
+    // clatter of the landing item can draw monsters away
+    make_noise(x, y, THROW_NOISE, game);
+
+    match (item.as_ref().and_then(throw_damage), hit_id) {
+        (Some(damage), Some(target_id)) => {
+            // thrown weapon hits the target and falls at its feet
+            train(Skill::Ranged, game);
+            let damage = damage + objects[PLAYER].skill_level(Skill::Ranged, game)
+                - objects[target_id].defense(game);
+            if damage > 0 {
+                game.messages.add(
+                    format!("The {} hits {} for {} damage!", name, objects[target_id].name, damage),
+                    WHITE,
+                );
+                if let Some(xp) = objects[target_id].take_damage(damage, game) {
+                    award_xp(PLAYER, xp, game, objects);
+                }
+            } else {
+                game.messages.add(
+                    format!("The {} bounces off {} harmlessly.", name, objects[target_id].name),
+                    WHITE,
+                );
+            }
+        }
+        _ => {
+            game.messages.add(format!("The {} lands on the ground.", name), YELLOW);
+        }
+    }
+    true
+}
+
+// damage done by a thrown item, None if it is not a throwing weapon
+fn throw_damage(item: &Item) -> Option<i32> {
+    item.kind().and_then(item_def).and_then(|def| def.throw_damage)
+}
+
+// what using an item does, None for equipment and gold
+fn use_effect(item: &Item) -> Option<UseEffect> {
+    item.kind().and_then(item_def).and_then(|def| def.effect)
+}
+
+// scrolls are spells, so perks that help magic apply to them
+fn is_scroll(effect: UseEffect) -> bool {
+    !is_potion(effect)
+}
+
+// roll to see if spell efficiency keeps a used scroll
+fn keeps_scroll(game: &Game, objects: &[Object]) -> bool {
+    objects[PLAYER].has_perk(Perk::SpellEfficiency, game)
+        && rand::thread_rng().gen_range(0, 100) < SPELL_EFFICIENCY_CHANCE
+}
+
+// potions shatter when thrown instead of landing
+fn is_potion(effect: UseEffect) -> bool {
+    matches!(effect, UseEffect::Heal)
 }
 
 pub fn use_item(inventory_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
     
-    use Item::*;
+    use UseEffect::*;
+    let item = &game.inventory[inventory_id];
+    let effect = item.item.as_ref().and_then(use_effect);
     // call the 'use_function' if defined 
-    if let Some(item) = game.inventory[inventory_id].item {
-        let on_use = match item {
-            Heal => cast_heal,
-            Lightning => cast_lightning,
-            Confuse => cast_confuse,
-            Fireball => cast_fireball,
-            Sword => toggle_equipment,
-            Shield => toggle_equipment,
-            Bow => toggle_equipment,
-    		Wand => toggle_equipment,
-    		Shield => toggle_equipment,
-    		Helmet => toggle_equipment,
-    		ChestPiece => toggle_equipment,
-    		Legs => toggle_equipment,
-    		Boots => toggle_equipment,
-    		Gloves => toggle_equipment,
-    		Cape => toggle_equipment,
-    		Ring => toggle_equipment,
-        };
+    let on_use = match effect {
+        Some(Heal) => cast_heal,
+        Some(Lightning) => cast_lightning,
+        Some(Confuse) => cast_confuse,
+        Some(Fireball) => cast_fireball,
+        Some(Charm) => cast_charm,
+        Some(Fear) => cast_fear,
+        Some(Paralyze) => cast_paralyze,
+        None if item.equipment.is_some() => toggle_equipment,
+        None => {
+            game.messages.add(format!("The {} cannot be used!", item.name), WHITE);
+            return;
+        }
+    };
+    let scroll = effect.is_some_and(is_scroll);
+
+    let result = on_use(inventory_id, tcod, game, objects);
+    // casting from a scroll trains evocation
+    if scroll && matches!(result, UseResult::UsedUp) {
+        train(Skill::Evocation, game);
+    }
+
+    match result {
+        UseResult::UsedUp if scroll && keeps_scroll(game, objects) => {
+            // spell efficiency can save the scroll from crumbling
+            game.messages.add(
+                format!("The {} stays intact!", game.inventory[inventory_id].name),
+                LIGHT_CYAN,
+            );
+        }
+        UseResult::UsedUp => {
+            // destroy after use, unless cancelled
+            game.inventory.remove(inventory_id);
+        }
+        UseResult::UsedAndKept => {} // do nothing
+        UseResult::Cancelled => {
+            game.messages.add("Cancelled", WHITE);
+        }
+    }
+}
+
+/*
+ *  Monsters and items
+ */
 
-        match on_use(inventory_id, tcod, game, objects) {
-            UseResult::UsedUp => {
-                // destroy after use, unless cancelled
-                game.inventory.remove(inventory_id);
+// function for monsters that carry items to pick up whatever they are standing on
+pub fn monsters_pick_up(tcod: &Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    // picking up removes the item from the list, so look again after each one
+    while let Some((monster_id, item_id)) = find_pick_up(objects) {
+        let mut item = objects.swap_remove(item_id);
+        // the monster may have been the last object, moved into the item's place
+        let monster_id = if monster_id == objects.len() { item_id } else { monster_id };
+        let monster = &objects[monster_id];
+
+        // put on equipment if nothing is worn there yet
+        if let Some(equipment) = item.equipment.as_mut() {
+            if get_equipped_in_slot(equipment.slot, &monster.inventory).is_none()
+                && meets_requirements(equipment, monster, game)
+            {
+                equipment.equipped = true;
+            }
+        }
+        if tcod.fov.is_in_fov(monster.x, monster.y) {
+            game.messages.add(format!("The {} picks up a {}.", monster.name, item.name), LIGHT_GREY);
+        }
+        item.always_visible = false;
+        objects[monster_id].inventory.push(item);
+    }
+}
+
+// find a living monster that carries items standing on an item it has room for
+fn find_pick_up(objects: &[Object]) -> Option<(usize, usize)> {
+    objects
+        .iter()
+        .enumerate()
+        .filter(|(_, monster)| {
+            monster.alive
+                && monster.inventory.len() < MAX_MONSTER_INVENTORY
+                && monster.kind.as_deref().and_then(monster_def).is_some_and(|def| def.carries_items)
+        })
+        .find_map(|(monster_id, monster)| {
+            objects
+                .iter()
+                .position(|object| {
+                    object.pos() == monster.pos() && object.item.as_ref().is_some_and(|item| !matches!(item, Item::Gold(_)))
+                })
+                .map(|item_id| (monster_id, item_id))
+        })
+}
+
+// spell a monster can cast by reading a scroll
+fn scroll_spell(effect: UseEffect) -> Option<Spell> {
+    match effect {
+        UseEffect::Lightning => Some(Spell::Lightning),
+        UseEffect::Fireball => Some(Spell::Fireball),
+        UseEffect::Confuse => Some(Spell::Confuse),
+        // a monster has no use for charming, scaring or paralyzing its own side
+        _ => None,
+    }
+}
+
+// function for a monster to drink or read something it carries when it helps, returns true if it did
+pub fn monster_use_items(monster_id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    for index in 0..objects[monster_id].inventory.len() {
+        let effect = match objects[monster_id].inventory[index].item.as_ref().and_then(use_effect) {
+            Some(effect) => effect,
+            None => continue,
+        };
+        let used = match effect {
+            // drink a potion when badly hurt
+            UseEffect::Heal => {
+                let monster = &objects[monster_id];
+                let max_hp = monster.max_hp(game);
+                let badly_hurt = monster.fighter.is_some_and(|f| f.hp * 100 < max_hp * MONSTER_HEAL_PERCENT);
+                if badly_hurt {
+                    game.messages.add(
+                        format!("The {} drinks a {}.", monster.name, monster.inventory[index].name),
+                        LIGHT_VIOLET,
+                    );
+                    heal_target(monster_id, monster_id, game, objects);
+                }
+                badly_hurt
             }
-            UseResult::UsedAndKept => {} // do nothing
-            UseResult::Cancelled => {
-                game.messages.add("Cancelled", WHITE);
+            // read a scroll now and then when there is a target for it
+            _ => match scroll_spell(effect) {
+                Some(spell) if rand::thread_rng().gen_range(0, 100) < MONSTER_SCROLL_CHANCE => {
+                    monster_cast(monster_id, spell, MONSTER_SCROLL_RANGE, game, objects)
+                }
+                _ => false,
+            },
+        };
+        if used {
+            let used_item = objects[monster_id].inventory.remove(index);
+            if is_scroll(effect) {
+                game.messages.add(format!("The {} crumbles to dust.", used_item.name), LIGHT_GREY);
             }
+            return true;
         }
-    } else {
-        game.messages.add(
-            format!("The {} cannot be used!", game.inventory[inventory_id].name),
-            WHITE
-        );
     }
-    
+    false
 }
 
 // function to equip / unequip items
-fn toggle_equipment(inventory_id: usize, _tcod: &mut Tcod, game: &mut Game, _objects: &mut [Object]) -> UseResult {
+fn toggle_equipment(inventory_id: usize, _tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) -> UseResult {
     
     let equipment = match game.inventory[inventory_id].equipment {
         Some(equipment) => equipment,
         None => return UseResult::Cancelled,
     };
+
+    // check attribute requirements before putting anything on
+    if !equipment.equipped && !meets_requirements(&equipment, &objects[PLAYER], game) {
+        game.messages.add(
+            format!("You lack the attributes to use the {}.", game.inventory[inventory_id].name),
+            RED,
+        );
+        return UseResult::Cancelled;
+    }
     
     if let Some(current) = get_equipped_in_slot(equipment.slot, &game.inventory) {
         game.inventory[current].unequip(&mut game.messages);
@@ -192,6 +568,19 @@ fn toggle_equipment(inventory_id: usize, _tcod: &mut Tcod, game: &mut Game, _obj
     UseResult::UsedAndKept
 }
 
+// check if an object's attributes are high enough to use equipment
+fn meets_requirements(equipment: &Equipment, wearer: &Object, game: &Game) -> bool {
+    wearer.attributes(game).meets(&equipment.required)
+}
+
+// total weight of everything in inventory
+fn inventory_weight(inventory: &[Object]) -> i32 {
+    inventory
+        .iter()
+        .map(|item| item.equipment.map_or(0, |e| e.weight))
+        .sum()
+}
+
 // get current equipment in a slot -- return index in object list
 fn get_equipped_in_slot(slot: Slot, inventory: &[Object]) -> Option<usize> {
     
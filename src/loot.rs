@@ -0,0 +1,80 @@
+use rand::Rng;
+use rand::distributions::{IndependentSample, Weighted, WeightedChoice};
+use serde::Deserialize;
+
+use crate::object::{Object, Transition, from_dungeon_level};
+use crate::item::{Item, create_item};
+use crate::monster::monster_def;
+use crate::game::Game;
+
+const GOLD_PER_LEVEL: i32 = 5; // coins in a pile grow with dungeon level
+
+// one roll on a loot table
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum Loot {
+    Nothing,
+    Gold,
+    Item(String),  // id of an item definition
+}
+
+// entry in a monster's loot table, weighted by dungeon level
+#[derive(Clone, Debug, Deserialize)]
+pub struct LootDrop {
+    pub drop: Loot,
+    pub chance: Vec<Transition>,
+}
+
+// random amount of gold for a pile on this level
+pub fn gold_amount(level: u32) -> i32 {
+    let max = GOLD_PER_LEVEL * level as i32;
+    rand::thread_rng().gen_range(1, max + 2)
+}
+
+// function to roll a dying monster's loot, items wait in game.loot until they can be placed
+pub fn drop_loot(monster: &Object, game: &mut Game) {
+    let def = match monster.kind.as_deref().and_then(monster_def) {
+        Some(def) => def,
+        None => return,
+    };
+
+    let table = &mut def
+        .loot
+        .iter()
+        .map(|entry| Weighted {
+            weight: from_dungeon_level(&entry.chance, game.dungeon_level),
+            item: entry.drop.clone(),
+        })
+        .collect::<Vec<_>>();
+    // nothing can drop on this level
+    if table.iter().all(|entry| entry.weight == 0) {
+        return;
+    }
+    let loot_choice = WeightedChoice::new(table);
+
+    for _ in 0..def.loot_rolls {
+        let item = match loot_choice.ind_sample(&mut rand::thread_rng()) {
+            Loot::Nothing => continue,
+            Loot::Gold => Item::Gold(gold_amount(game.dungeon_level)),
+            Loot::Item(kind) => Item::Kind(kind),
+        };
+        let mut object = create_item(item, monster.x, monster.y);
+        object.always_visible = true;
+        game.loot.push(object);
+    }
+}
+
+// function to drop everything a dying monster picked up, it lands where the monster fell
+pub fn drop_inventory(monster: &mut Object, game: &mut Game) {
+    for mut item in monster.inventory.drain(..) {
+        if let Some(equipment) = item.equipment.as_mut() {
+            equipment.equipped = false;
+        }
+        item.set_pos(monster.x, monster.y);
+        game.loot.push(item);
+    }
+}
+
+// function to move any dropped loot into the object list so it is drawn and can be picked up
+pub fn place_loot(game: &mut Game, objects: &mut Vec<Object>) {
+    objects.append(&mut game.loot);
+}
@@ -1,11 +1,13 @@
 
 
 use tcod::colors::*;
+use serde::{Deserialize, Serialize};
 
 use crate::game::{Tcod, Game};
-use crate::object::{Object, closest_monster};
-use crate::monster_ai::Ai;
-use crate::item::UseResult;
+use crate::object::{Object, closest_monster, closest_hostile, award_xp, in_line_of_sight};
+use crate::monster_ai::{Ai, AiChange, alert, can_see, change_ai};
+use crate::faction::{Faction, TempFaction};
+use crate::item::{UseEffect, UseResult};
 use crate::graphics::{target_tile, target_monster};
 
 const HEAL_AMOUNT: i32 = 40;
@@ -13,10 +15,29 @@ const LIGHTNING_RANGE: i32 = 5;
 const LIGHTNING_DAMAGE: i32 = 40;
 const CONFUSE_RANGE: i32 = 8;
 const CONFUSE_NUM_TURNS: i32 = 10;
+const CHARM_RANGE: i32 = 6;
+const CHARM_NUM_TURNS: i32 = 30;
+const FEAR_RANGE: i32 = 8;
+const FEAR_NUM_TURNS: i32 = 12;
+const PARALYZE_RANGE: i32 = 6;
+const PARALYZE_NUM_TURNS: i32 = 6;
+const ENRAGE_NUM_TURNS: i32 = 15;
 const FIREBALL_RADIUS: i32 = 3; 
 const FIREBALL_DAMAGE: i32 = 25;
+const SPLASH_RADIUS: i32 = 1;
+const SPELL_POWER_SCALE: i32 = 3; // extra damage or healing per point of spell power
 const PLAYER: usize = 0;
 
+// spells monsters can cast at each other and the player
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Spell {
+    Heal,
+    Lightning,
+    Fireball,
+    Confuse,
+    Enrage,
+}
+
 // function to cast heal 
 pub fn cast_heal(_inventory_id: usize, _tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) -> UseResult {
     let player = &mut objects[PLAYER];
@@ -27,9 +48,9 @@ pub fn cast_heal(_inventory_id: usize, _tcod: &mut Tcod, game: &mut Game, object
             game.messages.add("You are already at full health!", RED);
             return UseResult::Cancelled;
         }
-        // do the heal 
+        // do the heal, stronger with more spell power
         game.messages.add("Your wounds start to heal!", LIGHT_VIOLET);
-        player.heal(HEAL_AMOUNT, game);
+        heal_target(PLAYER, PLAYER, game, objects);
         return UseResult::UsedUp;
     }
     // the if let condition failed for some reason 
@@ -39,16 +60,10 @@ pub fn cast_heal(_inventory_id: usize, _tcod: &mut Tcod, game: &mut Game, object
 // function to use lightning attack on nearest enemy to player
 pub fn cast_lightning(_inventory_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) -> UseResult {
     // find closest enemy inside max range
-    let monster_id = closest_monster(tcod, objects, LIGHTNING_RANGE);
+    let monster_id = closest_monster(tcod, &game.map, objects, LIGHTNING_RANGE);
     if let Some(monster_id) = monster_id {
         // damage it with spell
-        game.messages.add(
-            format!("A lightning bolt strikes {}! Damage is {} hit points.", objects[monster_id].name, LIGHTNING_DAMAGE),
-            LIGHT_BLUE,
-        );
-        if let Some(xp) = objects[monster_id].take_damage(LIGHTNING_DAMAGE, game) {
-            objects[PLAYER].fighter.as_mut().unwrap().xp += xp;
-        }
+        lightning_target(PLAYER, monster_id, game, objects);
         UseResult::UsedUp
     } else {
         // no enemy found in range
@@ -64,25 +79,98 @@ pub fn cast_confuse(_inventory_id: usize, tcod: &mut Tcod, game: &mut Game, obje
 
     // find closest enemy in range and confuse it
     let monster_id = target_monster(tcod, game, objects, Some(CONFUSE_RANGE as f32));
-    if let Some(monster_id) = monster_id {
-        // get old ai, make it Basic by default
-        let old_ai = objects[monster_id].ai.take().unwrap_or(Ai::Basic);
-        // replace ai with confused 
-        objects[monster_id].ai = Some(Ai::Confused {
-            previous_ai: Box::new(old_ai),
-            num_turns: CONFUSE_NUM_TURNS,
-        });
-        
-        game.messages.add(
-            format!("The eyes of {} look vacant, as they start to stumble around", objects[monster_id].name),
-            LIGHT_GREEN,
-        );
+    match monster_id {
+        Some(monster_id) if objects[monster_id].ai.is_some() => {
+            confuse_target(monster_id, game, objects);
+            UseResult::UsedUp
+        }
+        Some(monster_id) => {
+            game.messages.add(format!("The {} can't be confused.", objects[monster_id].name), RED);
+            UseResult::Cancelled
+        }
+        None => {
+            // no enemy in the max range
+            game.messages.add("No enemy is close enough to confuse.", RED);
+            UseResult::Cancelled
+        }
+    }
+}
 
-        UseResult::UsedUp
-    } else {
-        // no enemy in the max range
-        game.messages.add("No enemy is close enough to confuse.", RED);
-        UseResult::Cancelled
+// function to turn a monster into an ally of the player for a while
+pub fn cast_charm(_inventory_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) -> UseResult {
+
+    game.messages.add("Left click an enemy to charm it, or right click to cancel.", LIGHT_CYAN);
+
+    let monster_id = target_monster(tcod, game, objects, Some(CHARM_RANGE as f32));
+    match monster_id {
+        Some(monster_id) if !objects[PLAYER].is_hostile_to(&objects[monster_id]) => {
+            game.messages.add(format!("The {} is not hostile.", objects[monster_id].name), RED);
+            UseResult::Cancelled
+        }
+        Some(monster_id) if objects[monster_id].ai.is_none() => {
+            game.messages.add(format!("The {} can't be charmed.", objects[monster_id].name), RED);
+            UseResult::Cancelled
+        }
+        Some(monster_id) => {
+            charm_target(monster_id, game, objects);
+            UseResult::UsedUp
+        }
+        None => {
+            game.messages.add("No enemy is close enough to charm.", RED);
+            UseResult::Cancelled
+        }
+    }
+}
+
+// function to scare a monster into running from the player
+pub fn cast_fear(_inventory_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) -> UseResult {
+
+    game.messages.add("Left click an enemy to frighten it, or right click to cancel.", LIGHT_CYAN);
+
+    let monster_id = target_monster(tcod, game, objects, Some(FEAR_RANGE as f32));
+    match monster_id {
+        Some(monster_id) if !objects[PLAYER].is_hostile_to(&objects[monster_id]) => {
+            game.messages.add(format!("The {} is not hostile.", objects[monster_id].name), RED);
+            UseResult::Cancelled
+        }
+        Some(monster_id) if objects[monster_id].ai.is_none() => {
+            game.messages.add(format!("The {} can't be frightened.", objects[monster_id].name), RED);
+            UseResult::Cancelled
+        }
+        Some(monster_id) => {
+            fear_target(monster_id, game, objects);
+            UseResult::UsedUp
+        }
+        None => {
+            game.messages.add("No enemy is close enough to frighten.", RED);
+            UseResult::Cancelled
+        }
+    }
+}
+
+// function to stop a monster in its tracks for a few turns
+pub fn cast_paralyze(_inventory_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) -> UseResult {
+
+    game.messages.add("Left click an enemy to paralyze it, or right click to cancel.", LIGHT_CYAN);
+
+    let monster_id = target_monster(tcod, game, objects, Some(PARALYZE_RANGE as f32));
+    match monster_id {
+        Some(monster_id) if !objects[PLAYER].is_hostile_to(&objects[monster_id]) => {
+            game.messages.add(format!("The {} is not hostile.", objects[monster_id].name), RED);
+            UseResult::Cancelled
+        }
+        Some(monster_id) if objects[monster_id].ai.is_none() => {
+            game.messages.add(format!("The {} can't be paralyzed.", objects[monster_id].name), RED);
+            UseResult::Cancelled
+        }
+        Some(monster_id) => {
+            paralyze_target(monster_id, game, objects);
+            UseResult::UsedUp
+        }
+        None => {
+            game.messages.add("No enemy is close enough to paralyze.", RED);
+            UseResult::Cancelled
+        }
     }
 }
 
@@ -96,30 +184,255 @@ pub fn cast_fireball(_inventory_id: usize, tcod: &mut Tcod, game: &mut Game, obj
         None => return UseResult::Cancelled,  // target_tile returned none, user cancelled
     };
 
+    fireball_at(PLAYER, x, y, game, objects);
+    // return use result
+    UseResult::UsedUp
+}
+
+/*
+ *  Spell effects, for any caster and target
+ */
+
+// heal a target, stronger with more of the caster's spell power
+pub fn heal_target(caster_id: usize, target_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let heal_amount = HEAL_AMOUNT + objects[caster_id].spell_power(game) * SPELL_POWER_SCALE;
+    objects[target_id].heal(heal_amount, game);
+}
+
+// strike a target with lightning from the caster
+pub fn lightning_target(caster_id: usize, target_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let damage = LIGHTNING_DAMAGE + objects[caster_id].spell_power(game) * SPELL_POWER_SCALE;
+    game.messages.add(
+        format!("A lightning bolt strikes {}! Damage is {} hit points.", objects[target_id].name, damage),
+        LIGHT_BLUE,
+    );
+    if let Some(xp) = objects[target_id].take_damage(damage, game) {
+        award_xp(caster_id, xp, game, objects);
+    }
+}
+
+// explode a fireball from the caster at a position
+pub fn fireball_at(caster_id: usize, x: i32, y: i32, game: &mut Game, objects: &mut [Object]) {
     game.messages.add(
         format!("The fireball explodes, burning everything within {} tiles!", FIREBALL_RADIUS),
         ORANGE,
     );
-    
+
+    let damage = FIREBALL_DAMAGE + objects[caster_id].spell_power(game) * SPELL_POWER_SCALE;
+    let caster_faction = objects[caster_id].faction();
     let mut xp_to_gain = 0;  // hold sum of xp from multiple targets
     // go through all objects and see if they are in blast radius 
-    for (id, obj) in objects.iter_mut().enumerate() {
+    for obj in objects.iter_mut() {
         if obj.distance(x, y) <= FIREBALL_RADIUS as f32 && obj.fighter.is_some() {
             game.messages.add(
-                format!("The {} gets burned for {} hit points!", obj.name, FIREBALL_DAMAGE),
+                format!("The {} gets burned for {} hit points!", obj.name, damage),
                 ORANGE,
             );
-            if let Some(xp) = obj.take_damage(FIREBALL_DAMAGE, game) {
-                // don't give player xp from hitting themselves
-                if id != PLAYER {
+            // no xp from burning the caster or their own side
+            let own_side = obj.faction() == caster_faction;
+            if let Some(xp) = obj.take_damage(damage, game) {
+                if !own_side {
                     // add to sum of xp
                     xp_to_gain += xp;
                 }
             }
         }
     }
-    // now add sum to player xp
-    objects[PLAYER].fighter.as_mut().unwrap().xp += xp_to_gain;
-    // return use result
-    UseResult::UsedUp
-}
\ No newline at end of file
+    // now add sum to caster's side xp
+    if xp_to_gain > 0 {
+        award_xp(caster_id, xp_to_gain, game, objects);
+    }
+}
+
+// confuse a target, monsters stumble around and fight anyone, the player loses control of where they go
+pub fn confuse_target(target_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let target = &mut objects[target_id];
+    if target_id == PLAYER {
+        target.confused_turns = CONFUSE_NUM_TURNS;
+        game.messages.add("Your head spins, you can't walk straight!", LIGHT_GREEN);
+        return;
+    }
+
+    // nests and anything else without a mind are left alone
+    let old_ai = match target.ai.take() {
+        Some(ai) => ai,
+        None => return,
+    };
+    // replace ai with confused 
+    target.ai = Some(Ai::Confused {
+        previous_ai: Box::new(old_ai),
+        num_turns: CONFUSE_NUM_TURNS,
+    });
+    // confused monsters lash out at anyone, and anyone will fight them
+    target.temp_faction = Some(TempFaction {
+        faction: Faction::Feral,
+        num_turns: CONFUSE_NUM_TURNS,
+    });
+
+    game.messages.add(
+        format!("The eyes of {} look vacant, as they start to stumble around", target.name),
+        LIGHT_GREEN,
+    );
+}
+
+// charm a monster into fighting for the player until it wears off
+pub fn charm_target(target_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let target = &mut objects[target_id];
+    // it knows about the player now, and goes back to whatever it was doing afterwards
+    let old_ai = match target.ai.take() {
+        Some(ai) => alert(ai),
+        None => return,
+    };
+    target.temp_faction = Some(TempFaction {
+        faction: Faction::Player,
+        num_turns: CHARM_NUM_TURNS,
+    });
+    target.ai = Some(Ai::Charmed {
+        previous_ai: Box::new(old_ai),
+        num_turns: CHARM_NUM_TURNS,
+    });
+    game.messages.add(
+        format!("The {} looks at you adoringly and joins your side!", target.name),
+        LIGHT_BLUE,
+    );
+}
+
+// frighten a monster into running from the player
+pub fn fear_target(target_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let target = &mut objects[target_id];
+    let old_ai = match target.ai.take() {
+        Some(ai) => alert(ai),
+        None => return,
+    };
+    target.ai = Some(Ai::Feared {
+        previous_ai: Box::new(old_ai),
+        num_turns: FEAR_NUM_TURNS,
+    });
+    game.messages.add(format!("The {} shrieks in terror!", target.name), LIGHT_GREEN);
+}
+
+// paralyze a monster so it loses its turns
+pub fn paralyze_target(target_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let target = &mut objects[target_id];
+    let old_ai = match target.ai.take() {
+        Some(ai) => alert(ai),
+        None => return,
+    };
+    target.ai = Some(Ai::Paralyzed {
+        previous_ai: Box::new(old_ai),
+        num_turns: PARALYZE_NUM_TURNS,
+    });
+    game.messages.add(format!("The {} freezes in place!", target.name), LIGHT_GREEN);
+}
+
+// drive a monster into a rage, it stops caring about its own safety
+// the caster can be the target, so this goes through change_ai
+pub fn enrage_target(target_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let target = &mut objects[target_id];
+    change_ai(target, AiChange::Enrage { num_turns: ENRAGE_NUM_TURNS });
+    game.messages.add(format!("The {} flies into a rage!", target.name), RED);
+}
+
+/*
+ *  Monster spellcasting
+ */
+
+// function for a monster to cast a spell if it has a good target in sight, returns true if it did
+pub fn monster_cast(caster_id: usize, spell: Spell, range: i32, game: &mut Game, objects: &mut [Object]) -> bool {
+    let target_id = match spell {
+        Spell::Heal => wounded_friend(caster_id, range, game, objects),
+        Spell::Enrage => hunting_friend(caster_id, range, game, objects),
+        _ => {
+            let caster = &objects[caster_id];
+            closest_hostile(caster.faction(), caster.pos(), objects, range as f32)
+        }
+    };
+    let target_id = match target_id {
+        Some(target_id) if in_line_of_sight(objects[caster_id].pos(), objects[target_id].pos(), &game.map) => target_id,
+        _ => return false,
+    };
+    // nests and anything else without a mind can't be confused
+    if spell == Spell::Confuse && objects[target_id].ai.is_none() {
+        return false;
+    }
+    // don't get caught in its own blast
+    if spell == Spell::Fireball && objects[caster_id].distance_to(&objects[target_id]) <= FIREBALL_RADIUS as f32 {
+        return false;
+    }
+
+    let (caster_name, target_name) = (objects[caster_id].name.clone(), objects[target_id].name.clone());
+    match spell {
+        Spell::Heal => {
+            game.messages.add(format!("The {} heals the {}.", caster_name, target_name), LIGHT_VIOLET);
+            heal_target(caster_id, target_id, game, objects);
+        }
+        Spell::Lightning => {
+            game.messages.add(format!("The {} calls down lightning!", caster_name), LIGHT_BLUE);
+            lightning_target(caster_id, target_id, game, objects);
+        }
+        Spell::Fireball => {
+            game.messages.add(format!("The {} hurls a fireball!", caster_name), ORANGE);
+            let (x, y) = objects[target_id].pos();
+            fireball_at(caster_id, x, y, game, objects);
+        }
+        Spell::Confuse => {
+            game.messages.add(format!("The {} casts a confusing spell at {}!", caster_name, target_name), LIGHT_GREEN);
+            confuse_target(target_id, game, objects);
+        }
+        Spell::Enrage => {
+            game.messages.add(format!("The {} bellows a war cry at the {}!", caster_name, target_name), ORANGE);
+            enrage_target(target_id, game, objects);
+        }
+    }
+    true
+}
+
+// find the closest badly hurt fighter on the caster's side, the caster included
+fn wounded_friend(caster_id: usize, range: i32, game: &Game, objects: &[Object]) -> Option<usize> {
+    let caster = &objects[caster_id];
+    objects
+        .iter()
+        .enumerate()
+        .filter(|(_, object)| {
+            object.alive
+                && object.faction() == caster.faction()
+                && caster.distance_to(object) <= range as f32
+                && object.fighter.is_some_and(|f| f.hp * 2 < object.max_hp(game))
+        })
+        .min_by(|(_, a), (_, b)| caster.distance_to(a).total_cmp(&caster.distance_to(b)))
+        .map(|(id, _)| id)
+}
+
+// find the closest fighter on the caster's side that is after the player and not already enraged, the caster included
+fn hunting_friend(caster_id: usize, range: i32, game: &Game, objects: &[Object]) -> Option<usize> {
+    let caster = &objects[caster_id];
+    objects
+        .iter()
+        .enumerate()
+        .filter(|(id, object)| {
+            object.alive
+                && object.faction() == caster.faction()
+                && caster.distance_to(object) <= range as f32
+                // the caster's own ai is taken out while it takes its turn
+                && (matches!(object.ai, Some(Ai::Basic | Ai::Ranged { .. })) || *id == caster_id)
+                && can_see(object, &objects[PLAYER], &game.map)
+        })
+        .min_by(|(_, a), (_, b)| caster.distance_to(a).total_cmp(&caster.distance_to(b)))
+        .map(|(id, _)| id)
+}
+
+// function to apply a thrown potion's effect to everything around where it shattered
+pub fn shatter_potion(potion: UseEffect, x: i32, y: i32, game: &mut Game, objects: &mut [Object]) {
+    let splashed = objects
+        .iter_mut()
+        .filter(|obj| obj.distance(x, y) <= SPLASH_RADIUS as f32 && obj.fighter.is_some());
+    for obj in splashed {
+        if potion == UseEffect::Heal {
+            game.messages.add(
+                format!("The {} is splashed with healing potion!", obj.name),
+                LIGHT_VIOLET,
+            );
+            obj.heal(HEAL_AMOUNT, game);
+        }
+    }
+}
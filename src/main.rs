@@ -8,6 +8,18 @@ mod menu;
 mod magic;
 mod game;
 mod monster;
+mod noise;
+mod perk;
+mod skill;
+mod loot;
+mod ally;
+mod faction;
+mod ability;
+mod boss;
+mod spawner;
+mod pathfinding;
+mod dijkstra;
+mod tactics;
 
 //use std::error::Error;
 //use std::fs::File;
@@ -26,6 +38,11 @@ use tcod::map::{FovAlgorithm, Map as FovMap};  // rename tcod Map type as FovMap
 use crate::menu::{main_menu};
 //use crate::graphics::{render_all, handle_keys};
 use crate::game::{Tcod, Game};
+use crate::monster::load_monster_defs;
+use crate::item::load_item_defs;
+use crate::boss::load_boss_defs;
+use crate::spawner::load_nest_defs;
+use crate::perk::load_perk_defs;
 
 const SCREEN_WIDTH: i32 = 100;   // orig 80
 const SCREEN_HEIGHT: i32 = 60;  // orig 50
@@ -41,6 +58,28 @@ const LIMIT_FPS: i32 = 20; // 20 fps maximum
 
 fn main() {
 
+    // check the item, monster, boss, nest and perk data up front so mistakes show before the game starts
+    if let Err(e) = load_item_defs() {
+        eprintln!("Error loading item definitions: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = load_monster_defs() {
+        eprintln!("Error loading monster definitions: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = load_boss_defs() {
+        eprintln!("Error loading boss definitions: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = load_nest_defs() {
+        eprintln!("Error loading nest definitions: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = load_perk_defs() {
+        eprintln!("Error loading perk definitions: {}", e);
+        std::process::exit(1);
+    }
+
     tcod::system::set_fps(LIMIT_FPS);
     
     // root initialization 
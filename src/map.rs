@@ -4,16 +4,21 @@ use rand::Rng;
 use tcod::colors::*;
 use serde::{Deserialize, Serialize};
 
-use crate::item::{Item, Slot, Equipment};
+use crate::item::{Item, ARENA_POTION, create_item, item_table};
 use crate::object::{Object, Fighter, Transition, DeathCallback, from_dungeon_level, is_blocked};
 use crate::monster_ai::Ai;
 use crate::monster::{ monster_table };
+use crate::loot::gold_amount;
+use crate::ally::place_mercenary;
+use crate::spawner::place_spawner;
+use crate::boss::{BossDef, Arena, create_boss};
 
 const MAP_WIDTH: i32 = 100;
 const MAP_HEIGHT: i32 = 53;
 const ROOM_MAX_SIZE: i32 = 10;
 const ROOM_MIN_SIZE: i32 = 6;
 const MAX_ROOMS: i32 = 30;
+const GOLD_CHANCE: u32 = 15;  // weight of a gold pile in the item table
 const PLAYER: usize = 0; // player will always be first object in list 
 
 /*
@@ -145,6 +150,17 @@ pub fn make_map(objects: &mut Vec<Object>, level: u32) -> Map {
         }
     }
     
+    // maybe put a mercenary in one of the rooms past the first
+    if rooms.len() > 1 {
+        let room = rooms[rand::thread_rng().gen_range(1, rooms.len())];
+        place_mercenary(room, &map, objects, level);
+    }
+    // and maybe a nest that keeps producing monsters
+    if rooms.len() > 1 {
+        let room = rooms[rand::thread_rng().gen_range(1, rooms.len())];
+        place_spawner(room, &map, objects, level);
+    }
+
     // create stairs at the center of last room 
     let (last_room_x, last_room_y) = rooms[rooms.len() - 1].center();
     let mut stairs = Object::new(last_room_x, last_room_y, '<', "stairs", WHITE, false);
@@ -154,6 +170,82 @@ pub fn make_map(objects: &mut Vec<Object>, level: u32) -> Map {
     map   // return the map 
 }
 
+// function to create a boss level, a small entrance room leading to the boss's arena
+pub fn make_boss_map(boss: &BossDef, objects: &mut Vec<Object>) -> Map {
+    let mut map = vec![vec![Tile::wall(); MAP_HEIGHT as usize]; MAP_WIDTH as usize];
+
+    let entrance = Rect::new(4, 22, 9, 9);
+    let arena = Rect::new(28, 6, 64, 40);
+    let (entrance_x, entrance_y) = entrance.center();
+    let (_, arena_y) = arena.center();
+
+    create_room(entrance, &mut map);
+    objects[PLAYER].set_pos(entrance_x, entrance_y);
+    create_arena(boss.arena, arena, &mut map);
+    create_h_tunnel(entrance_x, arena.x1 + 1, arena_y, &mut map);
+    create_v_tunnel(entrance_y, arena_y, entrance_x, &mut map);
+
+    // boss waits in front of the stairs at the far end
+    let stairs_x = arena.x2 - 2;
+    objects.push(create_boss(boss, stairs_x - 4, arena_y));
+    let mut stairs = Object::new(stairs_x, arena_y, '<', "stairs", WHITE, false);
+    stairs.always_visible = true;
+    objects.push(stairs);
+
+    // a couple of healing potions in the entrance for the fight ahead
+    for dx in [-1, 1] {
+        objects.push(create_item(Item::Kind(ARENA_POTION.into()), entrance_x + dx, entrance_y + 2));
+    }
+
+    map
+}
+
+// function to carve out a boss arena, the middle row from the left edge to the far end is always kept open
+fn create_arena(arena: Arena, room: Rect, map: &mut Map) {
+    let (center_x, center_y) = room.center();
+    match arena {
+        Arena::Pillars => {
+            create_room(room, map);
+            // 2x2 pillars in a grid, keeping clear of the middle row and the far end
+            for x in ((room.x1 + 4)..(room.x2 - 8)).step_by(6) {
+                for y in ((room.y1 + 3)..(room.y2 - 2)).step_by(5) {
+                    if y + 1 < center_y || y > center_y {
+                        fill_walls(Rect { x1: x, y1: y, x2: x + 1, y2: y + 1 }, map);
+                    }
+                }
+            }
+        }
+        Arena::Ring => {
+            create_room(room, map);
+            // solid block in the middle to fight around
+            fill_walls(Rect { x1: center_x - 8, y1: center_y - 5, x2: center_x + 8, y2: center_y + 5 }, map);
+        }
+        Arena::Cross => {
+            // a long hall crossed by a tall one
+            create_room(Rect { x1: room.x1, y1: center_y - 5, x2: room.x2, y2: center_y + 5 }, map);
+            create_room(Rect { x1: center_x - 6, y1: room.y1, x2: center_x + 6, y2: room.y2 }, map);
+        }
+        Arena::Gates => {
+            create_room(room, map);
+            // wall across the hall with three gaps to get through
+            for y in (room.y1 + 1)..room.y2 {
+                if y != room.y1 + 5 && y != center_y && y != room.y2 - 5 {
+                    map[center_x as usize][y as usize] = Tile::wall();
+                }
+            }
+        }
+    }
+}
+
+// function to turn every tile in a rectangle, edges included, into wall
+fn fill_walls(rect: Rect, map: &mut Map) {
+    for x in rect.x1..(rect.x2 + 1) {
+        for y in rect.y1..(rect.y2 + 1) {
+            map[x as usize][y as usize] = Tile::wall();
+        }
+    }
+}
+
 // function to add room to map 
 fn create_room(room: Rect, map: &mut Map) {
     // go through tiles in rectangle and make them passable
@@ -293,48 +385,12 @@ pub fn place_objects(room: Rect, map: &Map, objects: &mut Vec<Object>, level: u3
     // get random number of items 
     let num_items = rand::thread_rng().gen_range(0, max_items + 1);
 
-    // item random table
-    let item_chances = &mut [
-        Weighted {
-            weight: from_dungeon_level(
-                        &[Transition { level: 4, value: 5 }],
-                        level,
-                    ),
-            item: Item::Sword,
-        },
-        Weighted {
-            weight: from_dungeon_level(
-                        &[Transition { level: 8, value: 15 }],
-                        level,
-                    ),
-            item: Item::Shield,
-        },
-        Weighted {
-            weight: 35,
-            item: Item::Heal,
-        },
-        Weighted {
-            weight: from_dungeon_level(
-                        &[Transition { level: 4, value: 25 }],
-                        level,
-                    ),
-            item: Item::Lightning,
-        },
-        Weighted {
-            weight: from_dungeon_level(
-                        &[Transition { level: 6, value: 25 }],
-                        level,
-                    ),
-            item: Item::Fireball,
-        },
-        Weighted {
-            weight: from_dungeon_level(
-                        &[Transition { level: 2, value: 10 }],
-                        level,
-                    ),
-            item: Item::Confuse,
-        },
-    ];
+    // item random table, gold gets its amount when a pile is placed
+    let item_chances = &mut item_table(level);
+    item_chances.push(Weighted {
+        weight: GOLD_CHANCE,
+        item: Item::Gold(0),
+    });
     let item_choice = WeightedChoice::new(item_chances);
 
     for _ in 0..num_items {
@@ -344,82 +400,11 @@ pub fn place_objects(room: Rect, map: &Map, objects: &mut Vec<Object>, level: u3
 
         // only place if not blocked 
         if !is_blocked(x, y, map, objects) {
-            // create potion (70%)
-            let mut item = match item_choice.ind_sample(&mut rand::thread_rng()) {
-                Item::Sword => {
-                    // create a sword
-                    let mut object = Object::new(x, y, '/', "sword", CYAN, false);
-                    object.item = Some(Item::Sword);
-                    object.equipment = Some(Equipment { 
-                        equipped: false, 
-                        slot: Slot::RightHand,
-                        max_hp_bonus: 0,
-                        power_bonus: 3,
-                        defense_bonus: 0,
-                        magic_bonus: 0,
-                    });
-                    object
-                }
-                Item::Shield => {
-                    let mut object = Object::new(x, y, '[', "shield", DARKER_ORANGE, false);
-                    object.item = Some(Item::Shield);
-                    object.equipment = Some(Equipment { 
-                        equipped: false, 
-                        slot: Slot::LeftHand, 
-                        max_hp_bonus: 0,
-                        power_bonus: 0,
-                        defense_bonus: 1,
-                        magic_bonus: 0,
-                    });
-                    object
-                }
-                Item::Heal => {
-                    // create healing potion 
-                    let mut object = Object::new(x, y, '!', "healing potion", VIOLET, false);
-                    object.item = Some(Item::Heal);
-                    object    
-                }
-                Item::Lightning => {
-                    // create lightning bolt scroll (10%)
-                    let mut object = Object::new(
-                        x,
-                        y,
-                        '#',
-                        "scroll of lightning bolt",
-                        LIGHT_YELLOW,
-                        false,
-                    );
-                    object.item = Some(Item::Lightning);
-                    object
-                }
-                Item::Fireball => {
-                    // create fireball scroll (10%)
-                    let mut object = Object::new(
-                        x,
-                        y,
-                        '#',
-                        "scroll of fireball",
-                        ORANGE,
-                        false,
-                    );
-                    object.item = Some(Item::Fireball);
-                    object
-                }
-                Item::Confuse => {
-                    // create confuse scroll (10%) 
-                    let mut object = Object::new(
-                        x,
-                        y,
-                        '#',
-                        "scroll of confusion",
-                        PINK,
-                        false,
-                    );
-                    object.item = Some(Item::Confuse);
-                    object
-                }
+            let item = match item_choice.ind_sample(&mut rand::thread_rng()) {
+                Item::Gold(_) => Item::Gold(gold_amount(level)),
+                item => item,
             };
- 
+            let mut item = create_item(item, x, y);
             item.always_visible = true;
             objects.push(item);
         }
@@ -1,27 +1,353 @@
-
+use std::cmp;
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::sync::OnceLock;
 use rand::Rng;
 use rand::distributions::{IndependentSample, Weighted, WeightedChoice};
-use tcod::colors::*;
-use serde::{Deserialize, Serialize};
+use tcod::colors::{self, *};
+use serde::Deserialize;
 
-use crate::object::{Object, Fighter, Transition, DeathCallback, from_dungeon_level, is_blocked};
+use crate::object::{Object, Fighter, Attributes, Transition, DeathCallback, from_dungeon_level, is_blocked};
 use crate::monster_ai::Ai;
 use crate::map::{Map, Rect};
 use crate::game::{Tcod, Game};
+use crate::faction::Faction;
+use crate::loot::{Loot, LootDrop};
+use crate::item::item_def;
+use crate::ability::{Ability, check_ability};
+use crate::boss::boss_for_level;
+
+const ASLEEP_CHANCE: i32 = 40; // percent of monsters that start asleep
+const MONSTER_FILE: &str = "../data/monsters.json";
+const GROUP_FILE: &str = "../data/groups.json";
+const GROUP_SPREAD: i32 = 2; // how far group members can be placed from the first one
+const DEPTH_PER_MONSTER_LEVEL: i32 = 3; // monsters gain a level every 3 dungeon levels
+const LEVEL_UP_CHANCE: i32 = 20;        // percent chance a monster is a level above the usual
+const HP_PERCENT_PER_LEVEL: i32 = 15;
+const POWER_PERCENT_PER_LEVEL: i32 = 10;
+const XP_PERCENT_PER_LEVEL: i32 = 25;
+const ELITE_CHANCE: i32 = 6;            // percent
+const CHAMPION_CHANCE: i32 = 2;         // percent
+const OUT_OF_DEPTH_CHANCE: i32 = 3;     // percent chance a room is filled from deeper down
+const OUT_OF_DEPTH_LEVELS: u32 = 4;
+const WANDER_BASE_CHANCE: u32 = 2;      // per thousand each turn that a wandering monster shows up
+const WANDER_RAMP_TURNS: u32 = 100;     // the chance goes up by one every this many turns on a level
+const WANDER_MAX_CHANCE: u32 = 25;      // per thousand
+const WANDER_MIN_DISTANCE: f32 = 10.0;  // wanderers appear at least this far from the player
+const WANDER_SPOT_TRIES: i32 = 20;
+const PLAYER: usize = 0;
+pub const DEFAULT_SIGHT: i32 = 10;      // as far as the player's torch reaches, also for monsters without a kind
+
+// monster and group definitions, loaded once at startup
+static MONSTER_DEFS: OnceLock<Vec<MonsterDef>> = OnceLock::new();
+static GROUP_DEFS: OnceLock<Vec<GroupDef>> = OnceLock::new();
+
+// everything needed to create a kind of monster, read from the monster data file
+#[derive(Clone, Debug, Deserialize)]
+pub struct MonsterDef {
+    pub kind: String,                   // id other data files and saved monsters refer to it by
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    pub max_hp: i32,
+    pub defense: i32,
+    pub power: i32,
+    #[serde(default)]
+    pub magic: i32,
+    #[serde(default)]
+    pub stealth: i32,
+    pub xp: i32,
+    #[serde(default = "default_sight")]
+    pub sight: i32,                     // how far it can see the player and other monsters
+    #[serde(default = "Attributes::average")]
+    pub attributes: Attributes,
+    pub ai: Ai,
+    pub faction: Faction,
+    pub spawn_chance: Vec<Transition>,  // weight in the spawn table by dungeon level
+    #[serde(default)]
+    pub loot_rolls: u32,                // times the loot table is rolled on death
+    #[serde(default)]
+    pub loot: Vec<LootDrop>,
+    #[serde(default)]
+    pub abilities: Vec<Ability>,
+    #[serde(default)]
+    pub carries_items: bool,            // picks up, wears and uses items it walks over
+    #[serde(default)]
+    pub flee_hp_percent: i32,           // runs away when hurt below this percent of max hp, 0 never runs
+    #[serde(default)]
+    pub wanders: bool,                  // roams the level until it notices the player
+}
+
+// a band of monsters that spawn together, like an orc warband or a wolf pack
+#[derive(Clone, Debug, Deserialize)]
+pub struct GroupDef {
+    pub name: String,
+    pub members: Vec<GroupMember>,
+    pub spawn_chance: Vec<Transition>,  // weight in the spawn table by dungeon level
+}
+
+// how many of one kind of monster are in a group
+#[derive(Clone, Debug, Deserialize)]
+pub struct GroupMember {
+    pub kind: String,
+    pub min: u32,
+    pub max: u32,
+}
+
+// stronger versions of ordinary monsters
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Rank {
+    Normal,
+    Elite,
+    Champion,
+}
+
+// sight for monsters that don't set it in the data file
+fn default_sight() -> i32 {
+    DEFAULT_SIGHT
+}
+
+// one pick from the spawn table
+#[derive(Clone, Copy)]
+enum Spawn {
+    Single(&'static MonsterDef),
+    Group(&'static GroupDef),
+}
+
+/*
+ *  Loading monster definitions
+ */
+
+// function to read and check the monster and group data files, must be called once before any monsters are made
+// and after the item definitions, which monster loot is checked against
+pub fn load_monster_defs() -> Result<(), Box<dyn Error>> {
+    let defs: Vec<MonsterDef> = read_data_file(MONSTER_FILE)?;
+    validate_monster_defs(&defs).map_err(|e| format!("{}: {}", MONSTER_FILE, e))?;
+    let groups: Vec<GroupDef> = read_data_file(GROUP_FILE)?;
+    validate_group_defs(&groups, &defs).map_err(|e| format!("{}: {}", GROUP_FILE, e))?;
+
+    MONSTER_DEFS
+        .set(defs)
+        .map_err(|_| "monster definitions loaded twice")?;
+    GROUP_DEFS
+        .set(groups)
+        .map_err(|_| "group definitions loaded twice")?;
+    Ok(())
+}
+
+// function to read a json data file, errors say which file was wrong
+pub fn read_data_file<T: serde::de::DeserializeOwned>(path: &str) -> Result<T, Box<dyn Error>> {
+    let mut json = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut json))
+        .map_err(|e| format!("couldn't read {}: {}", path, e))?;
+    let data = serde_json::from_str(&json).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(data)
+}
+
+// check for mistakes serde can't catch, returns a description of the first one found
+fn validate_monster_defs(defs: &[MonsterDef]) -> Result<(), String> {
+    if defs.is_empty() {
+        return Err("no monsters defined".into());
+    }
+    for (i, def) in defs.iter().enumerate() {
+        if def.kind.is_empty() {
+            return Err(format!("'{}' has an empty kind", def.name));
+        }
+        if defs[..i].iter().any(|other| other.kind == def.kind) {
+            return Err(format!("{} is defined more than once", def.kind));
+        }
+        if def.name.is_empty() {
+            return Err(format!("{} has an empty name", def.kind));
+        }
+        if def.max_hp <= 0 {
+            return Err(format!("'{}' must have max_hp above 0", def.name));
+        }
+        if def.xp < 0 {
+            return Err(format!("'{}' can't give negative xp", def.name));
+        }
+        if def.sight <= 0 {
+            return Err(format!("'{}' must have sight above 0", def.name));
+        }
+        // an archer that keeps further back than it can shoot never fires
+        if let Ai::Ranged { range, distance } = def.ai {
+            if range <= 0 || distance < 0 || distance >= range {
+                return Err(format!("'{}' ranged ai needs range above 0 and distance from 0 to below range", def.name));
+            }
+        }
+        if !(0..=100).contains(&def.flee_hp_percent) {
+            return Err(format!("'{}' flee_hp_percent must be between 0 and 100", def.name));
+        }
+        check_transitions(&def.spawn_chance).map_err(|e| format!("'{}' spawn_chance {}", def.name, e))?;
+        for drop in &def.loot {
+            check_transitions(&drop.chance).map_err(|e| format!("'{}' loot chance {}", def.name, e))?;
+            if let Loot::Item(kind) = &drop.drop {
+                if item_def(kind).is_none() {
+                    return Err(format!("'{}' drops {}, which isn't defined", def.name, kind));
+                }
+            }
+        }
+        if def.loot_rolls > 0 && def.loot.is_empty() {
+            return Err(format!("'{}' has loot_rolls but no loot", def.name));
+        }
+        for &ability in &def.abilities {
+            check_ability(ability).map_err(|e| format!("'{}' {}", def.name, e))?;
+        }
+    }
+    if defs.iter().all(|def| from_dungeon_level(&def.spawn_chance, 1) == 0) {
+        return Err("no monster can spawn on the first level".into());
+    }
+    Ok(())
+}
+
+// check every group is made of monsters that exist, in sensible numbers
+fn validate_group_defs(groups: &[GroupDef], defs: &[MonsterDef]) -> Result<(), String> {
+    for group in groups {
+        if group.members.is_empty() {
+            return Err(format!("group '{}' has no members", group.name));
+        }
+        for member in &group.members {
+            if !defs.iter().any(|def| def.kind == member.kind) {
+                return Err(format!("group '{}' uses {}, which isn't defined", group.name, member.kind));
+            }
+            if member.min > member.max {
+                return Err(format!("group '{}' has min above max for {}", group.name, member.kind));
+            }
+        }
+        check_transitions(&group.spawn_chance).map_err(|e| format!("group '{}' spawn_chance {}", group.name, e))?;
+    }
+    Ok(())
+}
+
+// from_dungeon_level needs transitions sorted by level
+pub fn check_transitions(table: &[Transition]) -> Result<(), String> {
+    if table.iter().any(|transition| transition.level < 1) {
+        return Err("levels start at 1".into());
+    }
+    if table.windows(2).any(|pair| pair[0].level >= pair[1].level) {
+        return Err("levels must be in increasing order".into());
+    }
+    Ok(())
+}
+
+// all loaded monster definitions
+pub fn monster_defs() -> &'static [MonsterDef] {
+    MONSTER_DEFS.get().expect("monster definitions not loaded")
+}
+
+// all loaded group definitions
+pub fn group_defs() -> &'static [GroupDef] {
+    GROUP_DEFS.get().expect("group definitions not loaded")
+}
+
+// definition for one kind of monster
+pub fn monster_def(kind: &str) -> Option<&'static MonsterDef> {
+    monster_defs().iter().find(|def| def.kind == kind)
+}
+
+/*
+ *  Creating monsters
+ */
+
+// function to build a monster object from its definition
+pub fn create_monster(def: &MonsterDef, x: i32, y: i32) -> Object {
+    let mut monster = Object::new(x, y, def.glyph, &def.name, def.color, true);
+    monster.fighter = Some(Fighter {
+        base_max_hp: def.max_hp,
+        hp: def.max_hp,
+        base_defense: def.defense,
+        base_power: def.power,
+        base_magic: def.magic,
+        base_stealth: def.stealth,
+        xp: def.xp,
+        on_death: DeathCallback::Monster,
+        attributes: def.attributes,
+    });
+    monster.ai = Some(def.ai.clone());
+    monster.kind = Some(def.kind.clone());
+    monster.faction = def.faction;
+    monster
+}
+
+// roll the level a monster has this deep in the dungeon
+fn roll_monster_level(dungeon_level: u32) -> i32 {
+    let level = (dungeon_level as i32 + DEPTH_PER_MONSTER_LEVEL - 1) / DEPTH_PER_MONSTER_LEVEL;
+    if rand::thread_rng().gen_range(0, 100) < LEVEL_UP_CHANCE {
+        level + 1
+    } else {
+        level
+    }
+}
 
+// roll whether a monster is an elite or champion
+fn roll_rank() -> Rank {
+    let roll = rand::thread_rng().gen_range(0, 100);
+    if roll < CHAMPION_CHANCE {
+        Rank::Champion
+    } else if roll < CHAMPION_CHANCE + ELITE_CHANCE {
+        Rank::Elite
+    } else {
+        Rank::Normal
+    }
+}
+
+// function to create a monster scaled for the dungeon level, sometimes as an elite or champion
+pub fn spawn_monster(def: &MonsterDef, x: i32, y: i32, dungeon_level: u32) -> Object {
+    let mut monster = create_monster(def, x, y);
+    let level = roll_monster_level(dungeon_level);
+    let rank = roll_rank();
+    monster.level = level;
+
+    if let Some(fighter) = monster.fighter.as_mut() {
+        // each level past the first adds a share of the base stats
+        let extra_levels = level - 1;
+        fighter.base_max_hp += fighter.base_max_hp * extra_levels * HP_PERCENT_PER_LEVEL / 100;
+        fighter.base_power += fighter.base_power * extra_levels * POWER_PERCENT_PER_LEVEL / 100;
+        fighter.base_defense += extra_levels / 2;
+        fighter.xp += fighter.xp * extra_levels * XP_PERCENT_PER_LEVEL / 100;
+
+        match rank {
+            Rank::Elite => {
+                fighter.base_max_hp += fighter.base_max_hp / 2;
+                fighter.base_power += 2;
+                fighter.xp *= 2;
+            }
+            Rank::Champion => {
+                fighter.base_max_hp *= 2;
+                fighter.base_power += 4;
+                fighter.base_defense += 2;
+                fighter.xp *= 3;
+            }
+            Rank::Normal => (),
+        }
+        fighter.hp = fighter.base_max_hp;
+    }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
-pub enum Monster {
-	Orc,
-	Troll,
-	Bandit,
-	Warrior,
-	Zombie,
-	Demon,
+    // stand out from the rest of their kind
+    match rank {
+        Rank::Elite => {
+            monster.name = format!("elite {}", monster.name);
+            monster.color = colors::lerp(monster.color, GOLD, 0.5);
+        }
+        Rank::Champion => {
+            monster.name = format!("champion {}", monster.name);
+            monster.color = colors::lerp(monster.color, CRIMSON, 0.6);
+        }
+        Rank::Normal => (),
+    }
+    monster
 }
 
 pub fn monster_table(room: Rect, map: &Map, objects: &mut Vec<Object>, level: u32) {
-    
+
+    // now and then a room is filled with monsters from deeper down
+    let spawn_level = if rand::thread_rng().gen_range(0, 100) < OUT_OF_DEPTH_CHANCE {
+        level + OUT_OF_DEPTH_LEVELS
+    } else {
+        level
+    };
+
     // max monsters based on level
     let max_monsters = from_dungeon_level(
         &[
@@ -41,71 +367,23 @@ pub fn monster_table(room: Rect, map: &Map, objects: &mut Vec<Object>, level: u3
     // get random number of monsters
     let num_monsters = rand::thread_rng().gen_range(0, max_monsters + 1);
 
-	// orc chance random table
-    let orc_chance = from_dungeon_level(
-        &[
-            Transition { level: 1, value: 100 },
-            Transition { level: 3, value: 85 },
-            Transition { level: 5, value: 70 },
-            Transition { level: 7, value: 40 },
-            Transition { level: 11, value: 0 },
-        ],
-        level,
-    );
-
-	// troll chance random table
-    let troll_chance = from_dungeon_level(
-        &[
-            Transition { level: 3, value: 15 },
-            Transition { level: 5, value: 30 },
-            Transition { level: 7, value: 60 },
-            Transition { level: 11, value: 50 },
-            Transition { level: 14, value: 10 },
-            Transition { level: 17, value: 0 },
-        ],
-        level,
-    );
-
-    // bandit chance random table
-    let bandit_chance = from_dungeon_level(
-        &[
-            Transition { level: 11, value: 50 },
-            Transition { level: 14, value: 60 },
-            Transition { level: 17, value: 55 },
-            Transition { level: 21, value: 10 },
-        ],
-        level,
-    );
-
-    // warrior chance random table
-    let warrior_chance = from_dungeon_level(
-        &[
-            Transition { level: 14, value: 30 },
-            Transition { level: 17, value: 45 },
-            Transition { level: 21, value: 70 },
-        ],
-        level,
-    );
+    // monster random table, each kind and group weighted by its chance on this level
+    let monster_chances = &mut monster_defs()
+        .iter()
+        .map(|def| Weighted {
+            weight: from_dungeon_level(&def.spawn_chance, spawn_level),
+            item: Spawn::Single(def),
+        })
+        .chain(group_defs().iter().map(|group| Weighted {
+            weight: from_dungeon_level(&group.spawn_chance, spawn_level),
+            item: Spawn::Group(group),
+        }))
+        .collect::<Vec<_>>();
 
-    // monster random table
-    let monster_chances = &mut [
-        Weighted {
-            weight: orc_chance,
-            item: Monster::Orc,
-        },
-        Weighted {
-            weight: troll_chance,
-            item: Monster::Troll,
-        },
-        Weighted {
-            weight: bandit_chance,
-            item: Monster::Bandit,
-        },
-        Weighted {
-            weight: warrior_chance,
-            item: Monster::Warrior,
-        },
-    ];
+    // nothing lives this deep
+    if monster_chances.iter().all(|chance| chance.weight == 0) {
+        return;
+    }
 
     // create a weighted choice table from the chances
     let monster_choice = WeightedChoice::new(monster_chances);
@@ -115,77 +393,119 @@ pub fn monster_table(room: Rect, map: &Map, objects: &mut Vec<Object>, level: u3
         // get random spot for monster
         let x = rand::thread_rng().gen_range(room.x1 + 1, room.x2);
         let y = rand::thread_rng().gen_range(room.y1 + 1, room.y2);
-        
+
         // select monster based on random sample from this level's weighted choice table
-        let mut monster = match monster_choice.ind_sample(&mut rand::thread_rng()) {
-
-            Monster::Orc => {
-                // create orc
-                let mut orc = Object::new(x, y, 'o', "orc", DESATURATED_GREEN, true);
-                orc.fighter = Some(Fighter {
-                    base_max_hp: 20,
-                    hp: 20,
-                    base_defense: 0,
-                    base_power: 4,
-                    base_magic: 0,
-                    xp: 35,
-                    on_death: DeathCallback::Monster,
-                });
-                orc.ai = Some(Ai::Basic);
-                orc
+        let def = match monster_choice.ind_sample(&mut rand::thread_rng()) {
+            Spawn::Single(def) => def,
+            Spawn::Group(group) => {
+                // a group takes up the rest of the room
+                spawn_group(group, room, x, y, map, objects, spawn_level);
+                break;
             }
-            Monster::Troll => {
-                 // create troll 
-                let mut troll = Object::new(x, y, 'T', "troll", DARKER_GREEN, true);
-                troll.fighter = Some(Fighter {
-                    base_max_hp: 30,
-                    hp: 30,
-                    base_defense: 2,
-                    base_power: 8,
-                    base_magic: 0,
-                    xp: 100,
-                    on_death: DeathCallback::Monster,
-                });
-                troll.ai = Some(Ai::Basic);
-                troll
-            }
-            Monster::Bandit => {
-                 // create bandit 
-                let mut bandit = Object::new(x, y, 'B', "bandit", LIGHT_GREEN, true);
-                bandit.fighter = Some(Fighter {
-                    base_max_hp: 45,
-                    hp: 45,
-                    base_defense: 3,
-                    base_power: 10,
-                    base_magic: 0,
-                    xp: 175,
-                    on_death: DeathCallback::Monster,
-                });
-                bandit.ai = Some(Ai::Basic);
-                bandit
+        };
+
+        // if this is a good spot, make monster alive and put in list so it will be placed
+        if !is_blocked(x, y, map, objects) {
+            let asleep = rand::thread_rng().gen_range(0, 100) < ASLEEP_CHANCE;
+            place_unaware(spawn_monster(def, x, y, spawn_level), asleep, objects);
+        }
+    }
+}
+
+// function to now and then bring a wandering monster onto the level out of the player's sight,
+// more often the longer the player stays
+pub fn wandering_monsters(tcod: &Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    // nothing wanders into a boss arena while its boss is alive
+    if boss_for_level(game.dungeon_level, &game.slain_bosses).is_some() {
+        return;
+    }
+    game.level_turns += 1;
+    let chance = cmp::min(
+        WANDER_BASE_CHANCE + game.level_turns / WANDER_RAMP_TURNS,
+        WANDER_MAX_CHANCE,
+    );
+    if rand::thread_rng().gen_range(0, 1000) >= chance {
+        return;
+    }
+
+    // wanderers come alone, picked from the level's spawn table
+    let monster_chances = &mut monster_defs()
+        .iter()
+        .map(|def| Weighted {
+            weight: from_dungeon_level(&def.spawn_chance, game.dungeon_level),
+            item: def,
+        })
+        .collect::<Vec<_>>();
+    if monster_chances.iter().all(|chance| chance.weight == 0) {
+        return;
+    }
+    let def = WeightedChoice::new(monster_chances).ind_sample(&mut rand::thread_rng());
+
+    // find a free spot the player can't see, giving up if there is none
+    let (width, height) = (game.map.len() as i32, game.map[0].len() as i32);
+    for _ in 0..WANDER_SPOT_TRIES {
+        let x = rand::thread_rng().gen_range(0, width);
+        let y = rand::thread_rng().gen_range(0, height);
+        if !is_blocked(x, y, &game.map, objects)
+            && !tcod.fov.is_in_fov(x, y)
+            && objects[PLAYER].distance(x, y) >= WANDER_MIN_DISTANCE
+        {
+            // awake and on the move, but it doesn't know where the player is yet
+            place_unaware(spawn_monster(def, x, y, game.dungeon_level), false, objects);
+            return;
+        }
+    }
+}
+
+// function to spawn every member of a group clustered around a spot in a room
+fn spawn_group(group: &GroupDef, room: Rect, x: i32, y: i32, map: &Map, objects: &mut Vec<Object>, level: u32) {
+    // the group sleeps or wakes together, and is known by the index of its first member
+    let asleep = rand::thread_rng().gen_range(0, 100) < ASLEEP_CHANCE;
+    let group_id = objects.len();
+
+    for member in &group.members {
+        let def = match monster_def(&member.kind) {
+            Some(def) => def,
+            None => continue,
+        };
+        let count = rand::thread_rng().gen_range(member.min, member.max + 1);
+        for _ in 0..count {
+            // leave out anyone there is no room for
+            if let Some((x, y)) = free_spot_near(x, y, room, map, objects) {
+                let mut monster = spawn_monster(def, x, y, level);
+                monster.group = Some(group_id);
+                place_unaware(monster, asleep, objects);
             }
-            Monster::Warrior => {
-                 // create warrior 
-                let mut warrior = Object::new(x, y, 'W', "warrior", WHITE, true);
-                warrior.fighter = Some(Fighter {
-                    base_max_hp: 60,
-                    hp: 60,
-                    base_defense: 5,
-                    base_power: 12,
-                    base_magic: 0,
-                    xp: 250,
-                    on_death: DeathCallback::Monster,
-                });
-                warrior.ai = Some(Ai::Basic);
-                warrior
+        }
+    }
+}
+
+// function to find the closest free tile to a spot, staying inside the room
+fn free_spot_near(x: i32, y: i32, room: Rect, map: &Map, objects: &[Object]) -> Option<(i32, i32)> {
+    for distance in 0..(GROUP_SPREAD + 1) {
+        for dx in -distance..(distance + 1) {
+            for dy in -distance..(distance + 1) {
+                let (spot_x, spot_y) = (x + dx, y + dy);
+                let in_room = spot_x > room.x1 && spot_x < room.x2 && spot_y > room.y1 && spot_y < room.y2;
+                if in_room && !is_blocked(spot_x, spot_y, map, objects) {
+                    return Some((spot_x, spot_y));
+                }
             }
-            _ => unreachable!(),
-        }; 
-        
-        // if this is a good spot, make monster alive and put in list so it will be placed 
-        if !is_blocked(x, y, map, objects) {
-            monster.alive = true;
-            objects.push(monster);
         }
     }
-}
\ No newline at end of file
+    None
+}
+
+// function to add a new monster to the level, asleep or at least unaware of the player
+fn place_unaware(mut monster: Object, asleep: bool, objects: &mut Vec<Object>) {
+    monster.ai = monster.ai.take().map(|ai| {
+        let previous_ai = Box::new(ai);
+        if asleep {
+            Ai::Sleeping { previous_ai }
+        } else {
+            Ai::Wandering { previous_ai, destination: None }
+        }
+    });
+    monster.alive = true;
+    objects.push(monster);
+}
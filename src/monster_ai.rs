@@ -4,56 +4,375 @@ use rand::Rng;
 use tcod::colors::*;
 use serde::{Deserialize, Serialize};
 
-use crate::object::{Object, move_by, move_towards, mut_two};
+use crate::object::{Object, move_by, move_away, mut_two, closest_hostile, award_xp, in_line_of_sight, is_blocked, projectile_path};
+use crate::map::Map;
+use crate::monster::{monster_def, DEFAULT_SIGHT};
 use crate::game::{Tcod, Game};
+use crate::noise::{noise_at, heard_noise};
+use crate::skill::{Skill, train};
+use crate::faction::Faction;
+use crate::ability::{passive_abilities, active_abilities};
+use crate::item::monster_use_items;
+use crate::pathfinding::path_towards;
+use crate::dijkstra::DijkstraMap;
+use crate::tactics::{group_move, call_for_help};
 
 const PLAYER: usize = 0; 
+const SIGHT_AWARENESS: i32 = 12; // how much seeing the player counts toward noticing them
+const STEALTH_DIE: i32 = 10;     // random part of the player's stealth roll
+const FOLLOW_DISTANCE: f32 = 2.0;  // allies try to stay this close to the player
+const ALLY_AGGRO_RANGE: f32 = 6.0; // allies go after hostiles this close
+const GUARD_RANGE: f32 = 4.0;      // guards attack hostiles this close to their post
+const MONSTER_AGGRO_RANGE: f32 = 8.0; // monsters go after other hostile monsters this close
+const SEARCH_TURNS: i32 = 20;          // how long a monster looks for a player it lost sight of
+const WAKE_NOISE: i32 = 3;             // noise that wakes a sleeping monster without it noticing the player
+const INVESTIGATE_TURNS: i32 = 15;     // how long a monster spends looking for what made a noise
+const WANDER_TRIES: i32 = 20;
+const HURT_FLEE_TURNS: i32 = 10;
 
 // monster artificial intelligence
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Ai {
+    // hunting down the nearest thing it is hostile to
     Basic,
     Confused {
         previous_ai: Box<Ai>,
         num_turns: i32,
     },
+    // states of a monster that hasn't noticed the player yet
+    Sleeping {
+        previous_ai: Box<Ai>,
+    },
+    Wandering {
+        previous_ai: Box<Ai>,
+        destination: Option<(i32, i32)>,
+    },
+    Investigating {
+        x: i32,
+        y: i32,
+        turns_left: i32,
+        previous_ai: Box<Ai>,
+    },
+    // allies of the player
+    Follow,
+    Guard {
+        x: i32,
+        y: i32,
+    },
+    AttackTarget,
+    // waiting for the player to pay for their help
+    ForHire {
+        price: i32,
+    },
+    // shoots from a distance, backing off when anything gets closer than it likes
+    Ranged {
+        range: i32,
+        distance: i32,
+    },
+    // running away from the player for a while
+    Flee {
+        num_turns: i32,
+        previous_ai: Box<Ai>,
+    },
+    // mind-affecting states, the monster goes back to what it was doing when they wear off
+    Feared {
+        previous_ai: Box<Ai>,
+        num_turns: i32,
+    },
+    Charmed {
+        previous_ai: Box<Ai>,
+        num_turns: i32,
+    },
+    Paralyzed {
+        previous_ai: Box<Ai>,
+        num_turns: i32,
+    },
+    Enraged {
+        previous_ai: Box<Ai>,
+        num_turns: i32,
+    },
+}
+
+
+// where a monster last saw the player, and how much longer it will look there
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Memory {
+    pub x: i32,
+    pub y: i32,
+    pub turns_left: i32,
+}
+
+// a new state to wrap around a monster's ai, kept until the end of its turn if it is taking one
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AiChange {
+    Flee { num_turns: i32 },
+    Enrage { num_turns: i32 },
+}
+
+impl AiChange {
+    fn apply(self, ai: Ai) -> Ai {
+        match self {
+            AiChange::Flee { num_turns } => Ai::Flee {
+                num_turns,
+                previous_ai: Box::new(ai),
+            },
+            AiChange::Enrage { num_turns } => Ai::Enraged {
+                previous_ai: Box::new(ai),
+                num_turns,
+            },
+        }
+    }
+}
+
+// function to change a monster's ai, the ai is taken out while it takes its turn, so then
+// the change waits for the ai that turn ends up with instead of replacing it
+pub fn change_ai(monster: &mut Object, change: AiChange) {
+    match monster.ai.take() {
+        Some(ai) => monster.ai = Some(change.apply(ai)),
+        None => monster.pending_ai = Some(change),
+    }
 }
 
 
 pub fn ai_take_turn(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) {
     use Ai::*;
     
+    // temporary faction changes from confusion wear off over time
+    tick_temp_faction(monster_id, objects);
+    // abilities like regeneration work whatever the monster is doing
+    passive_abilities(monster_id, game, objects);
+
     // take() removes the value and puts None, but it will be replaced by return from functions 
     if let Some(ai) = objects[monster_id].ai.take() {
         let new_ai = match ai {
-            Basic => ai_basic(monster_id, tcod, game, objects), // returns Basic for new_ai
+            // items like a healing potion come first, then hurt monsters run,
+            // and special attacks are used instead of moving
+            Basic | Ranged { .. } if monster_use_items(monster_id, game, objects) => ai,
+            Basic | Ranged { .. } if should_flee(monster_id, game, objects) => {
+                start_fleeing(monster_id, tcod, game, objects, ai)
+            }
+            Basic | Ranged { .. } if active_abilities(monster_id, game, objects) => ai,
+            Basic => ai_basic(monster_id, game, objects), // returns Basic for new_ai
             Confused {
                 previous_ai,
                 num_turns,
             } => ai_confused(monster_id, tcod, game, objects, previous_ai, num_turns),
+            Sleeping { previous_ai } => ai_sleeping(monster_id, tcod, game, objects, previous_ai),
+            Wandering {
+                previous_ai,
+                destination,
+            } => ai_wandering(monster_id, tcod, game, objects, previous_ai, destination),
+            Investigating {
+                x,
+                y,
+                turns_left,
+                previous_ai,
+            } => ai_investigating(monster_id, tcod, game, objects, previous_ai, (x, y), turns_left),
+            Ranged { range, distance } => ai_ranged(monster_id, game, objects, range, distance),
+            Follow => ai_follow(monster_id, game, objects),
+            Guard { x, y } => ai_guard(monster_id, game, objects, x, y),
+            AttackTarget => ai_attack_target(monster_id, game, objects),
+            ForHire { price } => ForHire { price },  // just waits around
+            Flee {
+                num_turns,
+                previous_ai,
+            } => ai_flee(monster_id, game, objects, previous_ai, num_turns),
+            Feared {
+                previous_ai,
+                num_turns,
+            } => ai_feared(monster_id, tcod, game, objects, previous_ai, num_turns),
+            Charmed {
+                previous_ai,
+                num_turns,
+            } => ai_charmed(monster_id, tcod, game, objects, previous_ai, num_turns),
+            Paralyzed {
+                previous_ai,
+                num_turns,
+            } => ai_paralyzed(monster_id, tcod, game, objects, previous_ai, num_turns),
+            Enraged {
+                previous_ai,
+                num_turns,
+            } => ai_enraged(monster_id, tcod, game, objects, previous_ai, num_turns),
+        };
+        // something that happened during the turn may change the ai it ends up with,
+        // like a thief running off or a chieftain enraging itself
+        let new_ai = match objects[monster_id].pending_ai.take() {
+            Some(change) => change.apply(new_ai),
+            None => new_ai,
         };
-        objects[monster_id].ai = Some(new_ai);
+        if objects[monster_id].ai.is_none() && objects[monster_id].alive {
+            objects[monster_id].ai = Some(new_ai);
+        }
+    }
+
+    // a group member that knows about the player brings the rest of the group
+    if let (Some(group), Some(ai)) = (objects[monster_id].group, &objects[monster_id].ai) {
+        if !is_unaware(ai) {
+            alert_group(monster_id, group, tcod, game, objects);
+        }
+    }
+    // and one that is losing its fight calls for anyone else nearby
+    call_for_help(monster_id, tcod, game, objects);
+}
+
+// function to alert every unaware member of a group
+fn alert_group(monster_id: usize, group: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) {
+    let mut alerted = false;
+    for object in objects.iter_mut() {
+        if object.group == Some(group) && object.alive && object.ai.as_ref().is_some_and(is_unaware) {
+            object.ai = object.ai.take().map(alert);
+            alerted = true;
+        }
+    }
+    let (x, y) = objects[monster_id].pos();
+    if alerted && tcod.fov.is_in_fov(x, y) {
+        game.messages.add(format!("The {} calls out to the rest of its group!", objects[monster_id].name), ORANGE);
     }
 }
 
 // monster ai function to move and attack 
-pub fn ai_basic(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) -> Ai {
-    // a basic monster takes its turn. If you can see it, it can see you
-    let (monster_x, monster_y) = objects[monster_id].pos();
+pub fn ai_basic(monster_id: usize, game: &mut Game, objects: &mut [Object]) -> Ai {
+    // a basic monster takes its turn, going after the nearest thing it is hostile to
+    match basic_target(monster_id, game, objects) {
+        Some(target_id) => {
+            if target_id == PLAYER {
+                let (x, y) = objects[PLAYER].pos();
+                objects[monster_id].memory = Some(Memory { x, y, turns_left: SEARCH_TURNS });
+            }
+            if objects[monster_id].distance_to(&objects[target_id]) >= 2.0 {
+                // move towards target if far, together with its group if it has one
+                if !group_move(monster_id, target_id, game, objects) {
+                    let (target_x, target_y) = objects[target_id].pos();
+                    path_towards(monster_id, target_x, target_y, &game.map, objects);
+                }
+            } else {
+                // close enough to attack
+                let (monster, target) = mut_two(monster_id, target_id, objects);
+                if let Some(xp) = monster.attack(target, game) {
+                    award_xp(monster_id, xp, game, objects);
+                }
+            }
+        }
+        // charmed monsters tag along with the player when there is nothing to fight
+        None if objects[monster_id].faction() == Faction::Player => follow_player(monster_id, game, objects),
+        None => search(monster_id, game, objects),
+    }
+    Ai::Basic
+}
 
-    if tcod.fov.is_in_fov(monster_x, monster_y) {
-        if objects[monster_id].distance_to(&objects[PLAYER]) >= 2.0 {
-            // move towards player if far 
-            let (player_x, player_y) = objects[PLAYER].pos();
-            move_towards(monster_id, player_x, player_y, &game.map, objects);
+// monster ai that keeps its distance from its target and shoots when it has a clear line of fire
+fn ai_ranged(monster_id: usize, game: &mut Game, objects: &mut [Object], range: i32, distance: i32) -> Ai {
+    let target_id = match basic_target(monster_id, game, objects) {
+        Some(target_id) => target_id,
+        None => {
+            if objects[monster_id].faction() == Faction::Player {
+                follow_player(monster_id, game, objects);
+            } else {
+                search(monster_id, game, objects);
+            }
+            return Ai::Ranged { range, distance };
+        }
+    };
+    let (target_x, target_y) = objects[target_id].pos();
+    if target_id == PLAYER {
+        objects[monster_id].memory = Some(Memory { x: target_x, y: target_y, turns_left: SEARCH_TURNS });
+    }
 
-        } else if objects[PLAYER].fighter.map_or(false, |f| f.hp > 0) {  // checks if it is fighter
-            // close enough to attack (if player is alive)
-            let (monster, player) = mut_two(monster_id, PLAYER, objects);
-            monster.attack(player, game); 
+    let (x, y) = objects[monster_id].pos();
+    let target_distance = objects[monster_id].distance_to(&objects[target_id]);
+    // back off when the target closes in, if there is anywhere to go
+    if target_distance < distance as f32 {
+        let flee_map = DijkstraMap::walking(&[(target_x, target_y)], &game.map, objects).flee(&game.map, objects);
+        if let Some((step_x, step_y)) = flee_map.downhill(x, y, &game.map, objects) {
+            move_by(monster_id, step_x - x, step_y - y, &game.map, objects);
+            return Ai::Ranged { range, distance };
         }
     }
-    Ai::Basic
+
+    // only shoot when the target is the first thing the shot would hit
+    let (_, _, hit_id) = projectile_path((x, y), (target_x, target_y), &game.map, objects);
+    if target_distance >= 2.0 && target_distance <= range as f32 && hit_id == Some(target_id) {
+        game.messages.add(
+            format!("The {} shoots at {}!", objects[monster_id].name, objects[target_id].name),
+            WHITE,
+        );
+        let (monster, target) = mut_two(monster_id, target_id, objects);
+        if let Some(xp) = monster.attack(target, game) {
+            award_xp(monster_id, xp, game, objects);
+        }
+    } else if target_distance < 2.0 {
+        // cornered, so it fights up close
+        let (monster, target) = mut_two(monster_id, target_id, objects);
+        if let Some(xp) = monster.attack(target, game) {
+            award_xp(monster_id, xp, game, objects);
+        }
+    } else {
+        // get in range, or around whatever is in the way
+        path_towards(monster_id, target_x, target_y, &game.map, objects);
+    }
+    Ai::Ranged { range, distance }
+}
+
+// go to where the player was last seen, giving up after a while
+fn search(monster_id: usize, game: &Game, objects: &mut [Object]) {
+    let memory = match objects[monster_id].memory {
+        Some(memory) => memory,
+        None => return,
+    };
+    // forget about it once there or out of patience, or if no longer after the player
+    if objects[monster_id].pos() == (memory.x, memory.y)
+        || memory.turns_left <= 0
+        || !objects[monster_id].is_hostile_to(&objects[PLAYER])
+    {
+        objects[monster_id].memory = None;
+        return;
+    }
+    objects[monster_id].memory = Some(Memory { turns_left: memory.turns_left - 1, ..memory });
+    path_towards(monster_id, memory.x, memory.y, &game.map, objects);
+}
+
+// how far a monster can see
+fn sight_radius(monster: &Object) -> f32 {
+    monster
+        .kind
+        .as_deref()
+        .and_then(monster_def)
+        .map_or(DEFAULT_SIGHT, |def| def.sight) as f32
+}
+
+// check if a monster can see another object, with its own sight radius and line of sight
+pub fn can_see(monster: &Object, other: &Object, map: &Map) -> bool {
+    monster.distance_to(other) <= sight_radius(monster) && in_line_of_sight(monster.pos(), other.pos(), map)
+}
+
+// find the nearest hostile for a basic monster -- returns index of the target
+fn basic_target(monster_id: usize, game: &Game, objects: &[Object]) -> Option<usize> {
+    let monster = &objects[monster_id];
+    let mut closest_target = None;
+    let mut closest_dist = f32::MAX;
+
+    for (id, object) in objects.iter().enumerate() {
+        if id == monster_id || !monster.is_hostile_to(object) {
+            continue;
+        }
+        let dist = monster.distance_to(object);
+        // it has to see its target, and other monsters have to be close as well
+        let noticed = can_see(monster, object, &game.map) && (id == PLAYER || dist <= MONSTER_AGGRO_RANGE);
+        if noticed && dist < closest_dist {
+            closest_target = Some(id);
+            closest_dist = dist;
+        }
+    }
+    closest_target
+}
+
+// count down a temporary faction change, returning the monster to its own side when it runs out
+fn tick_temp_faction(monster_id: usize, objects: &mut [Object]) {
+    let monster = &mut objects[monster_id];
+    if let Some(mut temp) = monster.temp_faction {
+        temp.num_turns -= 1;
+        monster.temp_faction = if temp.num_turns > 0 { Some(temp) } else { None };
+    }
 }
 
 fn ai_confused(
@@ -67,13 +386,22 @@ fn ai_confused(
     
     if num_turns >= 0 {
         // still confused - move random direction and decrement turns
-        move_by(
-            monster_id,
-            rand::thread_rng().gen_range(-1, 2), // -1, 0 or 1 in x direction
-            rand::thread_rng().gen_range(-1, 2), // -1, 0 or 1 in y direction
-            &game.map,
-            objects,
-        );
+        let dx = rand::thread_rng().gen_range(-1, 2); // -1, 0 or 1 in x direction
+        let dy = rand::thread_rng().gen_range(-1, 2); // -1, 0 or 1 in y direction
+        let (x, y) = (objects[monster_id].x + dx, objects[monster_id].y + dy);
+        let target_id = objects
+            .iter()
+            .position(|object| object.fighter.is_some() && object.pos() == (x, y));
+        match target_id {
+            // lash out at whatever it stumbles into
+            Some(target_id) if target_id != monster_id => {
+                let (monster, target) = mut_two(monster_id, target_id, objects);
+                if let Some(xp) = monster.attack(target, game) {
+                    award_xp(monster_id, xp, game, objects);
+                }
+            }
+            _ => move_by(monster_id, dx, dy, &game.map, objects),
+        }
         // return modified confused ai 
         Ai::Confused {
             previous_ai: previous_ai,
@@ -89,3 +417,363 @@ fn ai_confused(
     }
 
 }
+
+// how much a monster that doesn't know about the player picked up this turn
+fn awareness(monster_id: usize, asleep: bool, game: &Game, objects: &[Object]) -> i32 {
+    let (monster_x, monster_y) = objects[monster_id].pos();
+    // noise heard this turn, faded by how far it travelled
+    let noise = noise_at(monster_x, monster_y, game);
+    if asleep {
+        // sleeping monsters only half hear and can't see
+        noise / 2
+    } else if can_see(&objects[monster_id], &objects[PLAYER], &game.map) {
+        // closer player is easier to spot
+        let distance = objects[monster_id].distance_to(&objects[PLAYER]) as i32;
+        noise + SIGHT_AWARENESS - distance
+    } else {
+        noise
+    }
+}
+
+// player's stealth roll against what an unaware monster picked up, returns true if it noticed the player
+fn notices_player(monster_id: usize, asleep: bool, tcod: &Tcod, game: &mut Game, objects: &[Object]) -> bool {
+    let awareness = awareness(monster_id, asleep, game, objects);
+    let stealth = objects[PLAYER].stealth(game);
+    let stealth_roll = rand::thread_rng().gen_range(0, STEALTH_DIE) + stealth;
+    if awareness > stealth_roll {
+        let (monster_x, monster_y) = objects[monster_id].pos();
+        if tcod.fov.is_in_fov(monster_x, monster_y) {
+            let msg = if asleep { "wakes up" } else { "notices you" };
+            game.messages.add(format!("The {} {}!", objects[monster_id].name, msg), ORANGE);
+        }
+        true
+    } else {
+        // slipping past something that nearly noticed trains stealth, only when a worse roll
+        // would have given the player away, so background noise doesn't train it for free
+        if awareness > stealth {
+            train(Skill::Stealth, game);
+        }
+        false
+    }
+}
+
+// monster ai that sleeps until the player makes enough noise
+fn ai_sleeping(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object], previous_ai: Box<Ai>) -> Ai {
+    if notices_player(monster_id, true, tcod, game, objects) {
+        // now aware of the player, restore normal ai
+        return *previous_ai;
+    }
+    // a loud enough noise still wakes it, but it only knows where the noise came from
+    let (x, y) = objects[monster_id].pos();
+    match heard_noise(x, y, game) {
+        Some(((noise_x, noise_y), volume)) if volume >= WAKE_NOISE => Ai::Investigating {
+            x: noise_x,
+            y: noise_y,
+            turns_left: INVESTIGATE_TURNS,
+            previous_ai,
+        },
+        _ => Ai::Sleeping { previous_ai },
+    }
+}
+
+// monster ai that is up and about but hasn't noticed the player, some kinds roam the level
+fn ai_wandering(
+    monster_id: usize,
+    tcod: &Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+    previous_ai: Box<Ai>,
+    destination: Option<(i32, i32)>,
+) -> Ai {
+    if notices_player(monster_id, false, tcod, game, objects) {
+        return *previous_ai;
+    }
+    // go and see what made a noise
+    let (x, y) = objects[monster_id].pos();
+    if let Some(((noise_x, noise_y), _)) = heard_noise(x, y, game) {
+        return Ai::Investigating {
+            x: noise_x,
+            y: noise_y,
+            turns_left: INVESTIGATE_TURNS,
+            previous_ai,
+        };
+    }
+
+    let wanders = objects[monster_id].kind.as_deref().and_then(monster_def).is_some_and(|def| def.wanders);
+    if !wanders {
+        return Ai::Wandering { previous_ai, destination: None };
+    }
+    // head somewhere new once it got where it was going
+    let destination = match destination {
+        Some(destination) if destination != (x, y) => Some(destination),
+        _ => wander_destination(game, objects),
+    };
+    if let Some((dest_x, dest_y)) = destination {
+        path_towards(monster_id, dest_x, dest_y, &game.map, objects);
+    }
+    Ai::Wandering { previous_ai, destination }
+}
+
+// random open spot somewhere on the level to wander to
+fn wander_destination(game: &Game, objects: &[Object]) -> Option<(i32, i32)> {
+    let (width, height) = (game.map.len() as i32, game.map[0].len() as i32);
+    (0..WANDER_TRIES)
+        .map(|_| (rand::thread_rng().gen_range(0, width), rand::thread_rng().gen_range(0, height)))
+        .find(|&(x, y)| !is_blocked(x, y, &game.map, objects))
+}
+
+// monster ai that goes to look at where a noise came from, then goes back to wandering
+fn ai_investigating(
+    monster_id: usize,
+    tcod: &Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+    previous_ai: Box<Ai>,
+    (x, y): (i32, i32),
+    turns_left: i32,
+) -> Ai {
+    if notices_player(monster_id, false, tcod, game, objects) {
+        return *previous_ai;
+    }
+    // a newer noise is more interesting
+    let (monster_x, monster_y) = objects[monster_id].pos();
+    let (x, y, turns_left) = match heard_noise(monster_x, monster_y, game) {
+        Some(((noise_x, noise_y), _)) => (noise_x, noise_y, INVESTIGATE_TURNS),
+        None => (x, y, turns_left),
+    };
+    if (monster_x, monster_y) == (x, y) || turns_left <= 0 {
+        return Ai::Wandering { previous_ai, destination: None };
+    }
+    path_towards(monster_id, x, y, &game.map, objects);
+    Ai::Investigating {
+        x,
+        y,
+        turns_left: turns_left - 1,
+        previous_ai,
+    }
+}
+
+// check if a hunting monster is hurt badly enough to run for it
+fn should_flee(monster_id: usize, game: &Game, objects: &[Object]) -> bool {
+    let monster = &objects[monster_id];
+    let flee_hp_percent = monster.kind.as_deref().and_then(monster_def).map_or(0, |def| def.flee_hp_percent);
+    let hurt = monster
+        .fighter
+        .is_some_and(|f| f.hp * 100 < monster.max_hp(game) * flee_hp_percent);
+    // only worth running from something it can see, and only once
+    !monster.fled && hurt && monster.is_hostile_to(&objects[PLAYER]) && can_see(monster, &objects[PLAYER], &game.map)
+}
+
+// function to send a hurt monster running, it comes back to the fight afterwards
+fn start_fleeing(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object], previous_ai: Ai) -> Ai {
+    objects[monster_id].fled = true;
+    let (x, y) = objects[monster_id].pos();
+    if tcod.fov.is_in_fov(x, y) {
+        game.messages.add(format!("The {} turns to flee!", objects[monster_id].name), ORANGE);
+    }
+    ai_flee(monster_id, game, objects, Box::new(previous_ai), HURT_FLEE_TURNS)
+}
+
+// monster ai that runs from the player until it feels safe again
+fn ai_flee(
+    monster_id: usize,
+    game: &mut Game,
+    objects: &mut [Object],
+    previous_ai: Box<Ai>,
+    num_turns: i32,
+) -> Ai {
+    if num_turns <= 0 {
+        return *previous_ai;
+    }
+    if !run_from_player(monster_id, game, objects) {
+        // cornered, so it fights whatever is next to it
+        let (x, y) = objects[monster_id].pos();
+        let faction = objects[monster_id].faction();
+        match closest_hostile(faction, (x, y), objects, 1.5) {
+            Some(target_id) => ally_fight(monster_id, target_id, game, objects),
+            None => {
+                let (player_x, player_y) = objects[PLAYER].pos();
+                move_away(monster_id, player_x, player_y, &game.map, objects);
+            }
+        }
+    }
+    Ai::Flee {
+        previous_ai,
+        num_turns: num_turns - 1,
+    }
+}
+
+// take a step along a flee map so it doesn't get cornered, returns false if there is nowhere to go
+fn run_from_player(monster_id: usize, game: &Game, objects: &mut [Object]) -> bool {
+    let (player_x, player_y) = objects[PLAYER].pos();
+    let flee_map = DijkstraMap::walking(&[(player_x, player_y)], &game.map, objects).flee(&game.map, objects);
+    let (x, y) = objects[monster_id].pos();
+    match flee_map.downhill(x, y, &game.map, objects) {
+        Some((step_x, step_y)) => {
+            move_by(monster_id, step_x - x, step_y - y, &game.map, objects);
+            true
+        }
+        None => false,
+    }
+}
+
+// monster ai that runs from the player in terror, cowering instead of fighting when cornered
+fn ai_feared(
+    monster_id: usize,
+    tcod: &Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+    previous_ai: Box<Ai>,
+    num_turns: i32,
+) -> Ai {
+    if num_turns <= 0 {
+        if tcod.fov.is_in_fov(objects[monster_id].x, objects[monster_id].y) {
+            game.messages.add(format!("The {} is no longer afraid!", objects[monster_id].name), RED);
+        }
+        return *previous_ai;
+    }
+    run_from_player(monster_id, game, objects);
+    Ai::Feared {
+        previous_ai,
+        num_turns: num_turns - 1,
+    }
+}
+
+// monster ai that hunts down the player's enemies until the charm wears off
+fn ai_charmed(
+    monster_id: usize,
+    tcod: &Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+    previous_ai: Box<Ai>,
+    num_turns: i32,
+) -> Ai {
+    // the charm also ends if something else, like confusion, changed its side
+    if num_turns <= 0 || objects[monster_id].faction() != Faction::Player {
+        objects[monster_id].temp_faction = None;
+        if tcod.fov.is_in_fov(objects[monster_id].x, objects[monster_id].y) {
+            game.messages.add(format!("The {} is no longer charmed!", objects[monster_id].name), RED);
+        }
+        return *previous_ai;
+    }
+    ai_basic(monster_id, game, objects);
+    Ai::Charmed {
+        previous_ai,
+        num_turns: num_turns - 1,
+    }
+}
+
+// monster ai that can't do anything until the paralysis wears off
+fn ai_paralyzed(
+    monster_id: usize,
+    tcod: &Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+    previous_ai: Box<Ai>,
+    num_turns: i32,
+) -> Ai {
+    if num_turns <= 0 {
+        if tcod.fov.is_in_fov(objects[monster_id].x, objects[monster_id].y) {
+            game.messages.add(format!("The {} can move again!", objects[monster_id].name), RED);
+        }
+        return *previous_ai;
+    }
+    Ai::Paralyzed {
+        previous_ai,
+        num_turns: num_turns - 1,
+    }
+}
+
+// monster ai that charges in without fleeing or stopping to use items until it calms down
+fn ai_enraged(
+    monster_id: usize,
+    tcod: &Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+    previous_ai: Box<Ai>,
+    num_turns: i32,
+) -> Ai {
+    if num_turns <= 0 {
+        if tcod.fov.is_in_fov(objects[monster_id].x, objects[monster_id].y) {
+            game.messages.add(format!("The {} calms down.", objects[monster_id].name), WHITE);
+        }
+        return *previous_ai;
+    }
+    ai_basic(monster_id, game, objects);
+    Ai::Enraged {
+        previous_ai,
+        num_turns: num_turns - 1,
+    }
+}
+
+// ally ai that fights nearby hostiles and otherwise stays with the player
+fn ai_follow(monster_id: usize, game: &mut Game, objects: &mut [Object]) -> Ai {
+    let ally_pos = objects[monster_id].pos();
+    let faction = objects[monster_id].faction();
+    if let Some(target_id) = closest_hostile(faction, ally_pos, objects, ALLY_AGGRO_RANGE) {
+        ally_fight(monster_id, target_id, game, objects);
+    } else {
+        follow_player(monster_id, game, objects);
+    }
+    Ai::Follow
+}
+
+// ally ai that stays at its post and attacks anything that comes near it
+fn ai_guard(monster_id: usize, game: &mut Game, objects: &mut [Object], x: i32, y: i32) -> Ai {
+    let faction = objects[monster_id].faction();
+    if let Some(target_id) = closest_hostile(faction, (x, y), objects, GUARD_RANGE) {
+        ally_fight(monster_id, target_id, game, objects);
+    } else if objects[monster_id].pos() != (x, y) {
+        // go back to post
+        path_towards(monster_id, x, y, &game.map, objects);
+    }
+    Ai::Guard { x, y }
+}
+
+// ally ai that goes after whatever the player last attacked
+fn ai_attack_target(monster_id: usize, game: &mut Game, objects: &mut [Object]) -> Ai {
+    let target_id = game
+        .player_target
+        .and_then(|target_pos| closest_hostile(Faction::Player, target_pos, objects, 1.5));
+    match target_id {
+        Some(target_id) => ally_fight(monster_id, target_id, game, objects),
+        None => follow_player(monster_id, game, objects),
+    }
+    Ai::AttackTarget
+}
+
+// move ally towards the player if it has fallen behind
+fn follow_player(monster_id: usize, game: &Game, objects: &mut [Object]) {
+    if objects[monster_id].distance_to(&objects[PLAYER]) > FOLLOW_DISTANCE {
+        let (player_x, player_y) = objects[PLAYER].pos();
+        path_towards(monster_id, player_x, player_y, &game.map, objects);
+    }
+}
+
+// move ally towards a hostile, attacking once close enough
+fn ally_fight(monster_id: usize, target_id: usize, game: &mut Game, objects: &mut [Object]) {
+    if objects[monster_id].distance_to(&objects[target_id]) >= 2.0 {
+        let (target_x, target_y) = objects[target_id].pos();
+        path_towards(monster_id, target_x, target_y, &game.map, objects);
+    } else {
+        let (ally, target) = mut_two(monster_id, target_id, objects);
+        if let Some(xp) = ally.attack(target, game) {
+            award_xp(monster_id, xp, game, objects);
+        }
+    }
+}
+
+// check if a monster hasn't noticed the player yet
+pub fn is_unaware(ai: &Ai) -> bool {
+    matches!(ai, Ai::Sleeping { .. } | Ai::Wandering { .. } | Ai::Investigating { .. })
+}
+
+// function to alert a monster that hasn't noticed the player yet, returns the ai it should have
+pub fn alert(ai: Ai) -> Ai {
+    match ai {
+        Ai::Sleeping { previous_ai }
+        | Ai::Wandering { previous_ai, .. }
+        | Ai::Investigating { previous_ai, .. } => *previous_ai,
+        ai => ai,
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+
+use crate::map::Map;
+use crate::game::Game;
+
+// how loud each player action is, in tiles of path distance it carries
+pub const WALK_NOISE: i32 = 4;
+pub const FIGHT_NOISE: i32 = 10;
+pub const THROW_NOISE: i32 = 6;
+
+const MAP_WIDTH: i32 = 100;
+const MAP_HEIGHT: i32 = 53;
+
+/*
+ *  Noise struct and related functions
+ */
+
+// a sound made somewhere on the map this turn
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Noise {
+    pub x: i32,
+    pub y: i32,
+    pub volume: i32,
+}
+
+// function to add a noise to this turn's list
+pub fn make_noise(x: i32, y: i32, volume: i32, game: &mut Game) {
+    game.noise.push(Noise { x, y, volume });
+}
+
+// function to get how loud this turn's noise is at a tile
+// sound travels around walls, so it fades by path distance instead of straight distance
+pub fn noise_at(x: i32, y: i32, game: &Game) -> i32 {
+    heard_noise(x, y, game).map_or(0, |(_, volume)| volume)
+}
+
+// function to get the loudest noise heard at a tile this turn, with where it came from
+pub fn heard_noise(x: i32, y: i32, game: &Game) -> Option<((i32, i32), i32)> {
+    game.noise
+        .iter()
+        .filter_map(|noise| {
+            path_distance((noise.x, noise.y), (x, y), noise.volume, &game.map)
+                .map(|dist| ((noise.x, noise.y), noise.volume - dist))
+        })
+        .max_by_key(|&(_, volume)| volume)
+}
+
+// breadth first search from start to goal through open tiles, gives up past max_dist
+fn path_distance(start: (i32, i32), goal: (i32, i32), max_dist: i32, map: &Map) -> Option<i32> {
+    let mut visited = vec![vec![false; MAP_HEIGHT as usize]; MAP_WIDTH as usize];
+    let mut queue = VecDeque::new();
+    visited[start.0 as usize][start.1 as usize] = true;
+    queue.push_back((start, 0));
+
+    while let Some(((x, y), dist)) = queue.pop_front() {
+        if (x, y) == goal {
+            return Some(dist);
+        }
+        if dist >= max_dist {
+            continue;
+        }
+        // check all 8 neighbours
+        for dx in -1..2 {
+            for dy in -1..2 {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= MAP_WIDTH || ny >= MAP_HEIGHT {
+                    continue;
+                }
+                if !visited[nx as usize][ny as usize] && !map[nx as usize][ny as usize].blocked {
+                    visited[nx as usize][ny as usize] = true;
+                    queue.push_back(((nx, ny), dist + 1));
+                }
+            }
+        }
+    }
+    None
+}
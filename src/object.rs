@@ -1,7 +1,9 @@
 
 use std::cmp;
+use rand::Rng;
 use tcod::colors::*;
 use tcod::console::*;
+use tcod::line::Line;
 use serde::{Deserialize, Serialize};
 
 use crate::menu::menu;
@@ -9,11 +11,31 @@ use crate::map::Map;
 use crate::item::{Equipment, Item};
 use crate::message::Messages;
 use crate::game::{Tcod, Game};
-use crate::monster_ai::Ai;
+use crate::monster_ai::{Ai, AiChange, Memory, alert};
+use crate::noise::{make_noise, WALK_NOISE, FIGHT_NOISE};
+use crate::perk::{Perk, PERK_LEVEL_INTERVAL, choose_perk};
+use crate::skill::{Skill, train, weapon_skill};
+use crate::loot::{drop_loot, drop_inventory};
+use crate::ally::hire;
+use crate::faction::{Faction, TempFaction};
+use crate::ability::{Poison, deserialize_poison, on_hit, on_damaged};
+use crate::boss::{BossState, check_boss_phase, boss_death};
+use crate::spawner::{SpawnerState, spawner_death};
+use crate::dijkstra::{DijkstraMap, UNREACHABLE};
 
 const LEVEL_UP_BASE: i32 = 200; // need 200 xp for first level up
 const LEVEL_UP_FACTOR: i32 = 150; // increase needed xp per each lvl up
 const LEVEL_SCREEN_WIDTH: i32 = 40;
+const AVERAGE_ATTRIBUTE: i32 = 10; // attributes above this give bonuses, below give penalties
+const HP_PER_CON: i32 = 20;
+const EVASION_PER_DEX: i32 = 3;    // percent chance to dodge per point
+const MAX_EVASION: i32 = 50;
+const CARRY_BASE: i32 = 20;
+const CARRY_PER_STR: i32 = 2;
+const TOUGHNESS_HP: i32 = 25;
+const SHADOW_STEALTH: i32 = 3;
+const DOUBLE_STRIKE_CHANCE: i32 = 25; // percent
+const ACCURACY_PER_SKILL: i32 = 3;    // percent less chance to be dodged per weapon skill level
 const PLAYER: usize = 0; // player will always be first object in list 
 
 /*
@@ -36,7 +58,33 @@ pub struct Object {
     pub equipment: Option<Equipment>,
     pub always_visible: bool,
     pub level: i32,
-    pub poisoned: bool,
+    // fields below are missing from older saves, which load with these defaults
+    #[serde(default, deserialize_with = "deserialize_poison")]
+    pub poisoned: Option<Poison>,
+    #[serde(default)]
+    pub kind: Option<String>,  // id of a monster's definition in the data file
+    #[serde(default)]
+    pub faction: Faction,
+    #[serde(default)]
+    pub temp_faction: Option<TempFaction>,  // charm or confusion can change sides for a while
+    #[serde(default)]
+    pub cooldown: i32,  // turns until a special attack can be used again
+    #[serde(default)]
+    pub confused_turns: i32,  // the player has no ai to confuse, so they count down here
+    #[serde(default)]
+    pub boss: Option<BossState>,
+    #[serde(default)]
+    pub group: Option<usize>,  // monsters spawned together share an id and wake up together
+    #[serde(default)]
+    pub inventory: Vec<Object>,  // items a monster carries, the player's are kept in game.inventory
+    #[serde(default)]
+    pub spawner: Option<SpawnerState>,
+    #[serde(default)]
+    pub memory: Option<Memory>,  // where a monster last saw the player
+    #[serde(default)]
+    pub pending_ai: Option<AiChange>,  // ai change waiting for the end of the monster's turn
+    #[serde(default)]
+    pub fled: bool,  // a hurt monster only runs away once, then fights to the end
 }
 
 impl Object {
@@ -55,7 +103,19 @@ impl Object {
             equipment: None,
             always_visible: false,
             level: 1,
-            poisoned: false,
+            poisoned: None,
+            kind: None,
+            faction: Faction::Neutral,
+            temp_faction: None,
+            cooldown: 0,
+            confused_turns: 0,
+            boss: None,
+            group: None,
+            inventory: vec![],
+            spawner: None,
+            memory: None,
+            pending_ai: None,
+            fled: false,
         }
     }
 
@@ -80,6 +140,16 @@ impl Object {
         (((x - self.x).pow(2) + (y - self.y).pow(2)) as f32).sqrt()
     }
 
+    // current side this object fights for, including temporary changes
+    pub fn faction(&self) -> Faction {
+        self.temp_faction.map_or(self.faction, |t| t.faction)
+    }
+
+    // check if this object would fight another living fighter
+    pub fn is_hostile_to(&self, other: &Object) -> bool {
+        other.fighter.is_some() && other.alive && self.faction().is_hostile_to(other.faction())
+    }
+
     // set color and then draw character for object 
     pub fn draw(&self, con: &mut dyn Console) {       // Console is a trait -- dyn highlights this
         con.set_default_foreground(self.color);
@@ -95,6 +165,11 @@ impl Object {
             }
         }
 
+        // getting hurt always alerts an unaware monster
+        if damage > 0 {
+            self.ai = self.ai.take().map(alert);
+        }
+
         // check for death, call death function
         if let Some(fighter) = self.fighter {
             if fighter.hp <= 0 {
@@ -103,10 +178,28 @@ impl Object {
                 return Some(fighter.xp);
             }
         }
+
+        // some monsters react to being hurt
+        if damage > 0 {
+            on_damaged(self, game);
+            check_boss_phase(self, game);
+        }
         None   
     }
 
-    pub fn attack(&mut self, target: &mut Object, game: &mut Game) {
+    // attack target, returns xp if the target was killed
+    pub fn attack(&mut self, target: &mut Object, game: &mut Game) -> Option<i32> {
+        // fighting is loud
+        make_noise(target.x, target.y, FIGHT_NOISE, game);
+        // nimble targets can dodge the attack entirely, skilled attackers are harder to dodge
+        let accuracy = self.skill_level(self.melee_skill(game), game) * ACCURACY_PER_SKILL;
+        if rand::thread_rng().gen_range(0, 100) < target.evasion(game) - accuracy {
+            game.messages.add(
+                format!("{} attacks {} but it dodges!", self.name, target.name),
+                WHITE,
+            );
+            return None;
+        }
         // simple attack formula
         let damage = self.power(game) - target.defense(game);
         if damage > 0 {
@@ -115,28 +208,41 @@ impl Object {
                 format!("{} attacks {} for {} damage!", self.name, target.name, damage),
                 WHITE,
             );
-            if let Some(xp) = target.take_damage(damage, game) {
-                // give exp to player -- take dmg only returns Some if death happens
-                self.fighter.as_mut().unwrap().xp += xp;
+            // take dmg only returns Some if death happens
+            let xp = target.take_damage(damage, game);
+            if xp.is_none() {
+                on_hit(self, target, game);
             }
-        } else {
-            game.messages.add(
-                format!("{} attacks {} but it has no effect!", self.name, target.name),
-                WHITE,
-            );
+            return xp;
         }
+        game.messages.add(
+            format!("{} attacks {} but it has no effect!", self.name, target.name),
+            WHITE,
+        );
+        None
     }
     
-    // calculate current attack power including equipment
+    // calculate current attributes including equipment
+    pub fn attributes(&self, game: &Game) -> Attributes {
+        let base_attributes = self.fighter.map_or(Attributes::average(), |f| f.attributes);
+        // add up all attribute bonus from equipped items
+        self.get_all_equipped(game)
+            .iter()
+            .fold(base_attributes, |total, e| total + e.attribute_bonus)
+    }
+
+    // calculate current attack power including strength, weapon skill and equipment
     pub fn power(&self, game: &Game) -> i32 {
         let base_power = self.fighter.map_or(0, |f| f.base_power);
+        let strength_bonus = modifier(self.attributes(game).strength);
+        let skill_bonus = self.skill_level(self.melee_skill(game), game);
         // add up all power bonus from equipped items
         let bonus: i32 = self
             .get_all_equipped(game)
             .iter()
             .map(|e| e.power_bonus)
             .sum();
-        base_power + bonus
+        base_power + strength_bonus + skill_bonus + bonus
     }
 
     // calculate current defense including equipment
@@ -151,16 +257,82 @@ impl Object {
         base_defense + bonus 
     }
 
-    // calculate current max_hp including equipment
+    // calculate current max_hp including constitution and equipment
     pub fn max_hp(&self, game: &Game) -> i32 {
         let base_max_hp = self.fighter.map_or(0, |f| f.base_max_hp);
+        let constitution_bonus = modifier(self.attributes(game).constitution) * HP_PER_CON;
         // add up equipment bonus
         let bonus: i32 = self
             .get_all_equipped(game)
             .iter()
             .map(|e| e.max_hp_bonus)
             .sum();
-        base_max_hp + bonus
+        let perk_bonus = if self.has_perk(Perk::Toughness, game) { TOUGHNESS_HP } else { 0 };
+        base_max_hp + constitution_bonus + bonus + perk_bonus
+    }
+
+    // calculate percent chance to dodge an attack from dexterity
+    pub fn evasion(&self, game: &Game) -> i32 {
+        let evasion = modifier(self.attributes(game).dexterity) * EVASION_PER_DEX;
+        evasion.clamp(0, MAX_EVASION)
+    }
+
+    // calculate current spell power from intelligence, wisdom and equipment
+    pub fn spell_power(&self, game: &Game) -> i32 {
+        let base_magic = self.fighter.map_or(0, |f| f.base_magic);
+        let attributes = self.attributes(game);
+        // intelligence counts fully, wisdom half as much
+        let attribute_bonus = modifier(attributes.intelligence) + modifier(attributes.wisdom) / 2;
+        let bonus: i32 = self
+            .get_all_equipped(game)
+            .iter()
+            .map(|e| e.magic_bonus)
+            .sum();
+        let skill_bonus = self.skill_level(Skill::Evocation, game);
+        base_magic + attribute_bonus + skill_bonus + bonus
+    }
+
+    // calculate how much equipment weight can be carried from strength
+    pub fn carry_capacity(&self, game: &Game) -> i32 {
+        CARRY_BASE + modifier(self.attributes(game).strength) * CARRY_PER_STR
+    }
+
+    // calculate current stealth, nimble fighters are quieter but worn equipment weight makes noise
+    pub fn stealth(&self, game: &Game) -> i32 {
+        let base_stealth = self.fighter.map_or(0, |f| f.base_stealth);
+        let dexterity_bonus = modifier(self.attributes(game).dexterity);
+        // subtract weight of everything equipped
+        let weight: i32 = self
+            .get_all_equipped(game)
+            .iter()
+            .map(|e| e.weight)
+            .sum();
+        let perk_bonus = if self.has_perk(Perk::Shadow, game) { SHADOW_STEALTH } else { 0 };
+        let skill_bonus = self.skill_level(Skill::Stealth, game);
+        base_stealth + dexterity_bonus - weight + perk_bonus + skill_bonus
+    }
+
+    // get level in a skill -- only the player trains skills
+    pub fn skill_level(&self, skill: Skill, game: &Game) -> i32 {
+        if self.name == "player" {
+            game.skills.get(skill).level
+        } else {
+            0
+        }
+    }
+
+    // skill used by the equipped weapon, fighting bare handed counts as blunt
+    pub fn melee_skill(&self, game: &Game) -> Skill {
+        self.get_all_equipped_items(game)
+            .iter()
+            .filter_map(weapon_skill)
+            .next()
+            .unwrap_or(Skill::Blunt)
+    }
+
+    // check if this object has learned a perk -- only the player has perks
+    pub fn has_perk(&self, perk: Perk, game: &Game) -> bool {
+        self.name == "player" && game.perks.contains(&perk)
     }
 
     pub fn heal(&mut self, amount: i32, game: &Game) {
@@ -205,19 +377,32 @@ impl Object {
         }
     }
     
-    // return list of all currently equipped items
-    pub fn get_all_equipped(&self, game: &Game) -> Vec<Equipment> {
+    // return everything this object carries
+    fn carried<'a>(&'a self, game: &'a Game) -> &'a [Object] {
         if self.name == "player" {
-            game.inventory
-                .iter()
-                .filter(|item| item.equipment.map_or(false, |e| e.equipped))
-                .map(|item| item.equipment.unwrap())
-                .collect()
+            &game.inventory
         } else {
-            // other objects have no equipment
-            vec![]
+            &self.inventory
         }
     }
+
+    // return item types of everything currently equipped
+    pub fn get_all_equipped_items(&self, game: &Game) -> Vec<Item> {
+        self.carried(game)
+            .iter()
+            .filter(|item| item.equipment.is_some_and(|e| e.equipped))
+            .filter_map(|item| item.item.clone())
+            .collect()
+    }
+
+    // return list of all currently equipped items
+    pub fn get_all_equipped(&self, game: &Game) -> Vec<Equipment> {
+        self.carried(game)
+            .iter()
+            .filter(|item| item.equipment.map_or(false, |e| e.equipped))
+            .map(|item| item.equipment.unwrap())
+            .collect()
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -228,6 +413,7 @@ pub enum PlayerAction {
 }
 
 // struct for level transitions 
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub struct Transition {
     pub level: u32,
     pub value: u32,
@@ -245,8 +431,74 @@ pub struct Fighter {
     pub base_defense: i32,
     pub base_power: i32,
     pub base_magic: i32,
+    #[serde(default)]
+    pub base_stealth: i32,
     pub xp: i32,
     pub on_death: DeathCallback,
+    #[serde(default = "Attributes::average")]
+    pub attributes: Attributes,
+}
+
+// primary attributes -- most fighter stats are derived from these
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Attributes {
+    pub strength: i32,
+    pub dexterity: i32,
+    pub constitution: i32,
+    pub intelligence: i32,
+    pub wisdom: i32,
+}
+
+impl Attributes {
+    // average attributes give no bonus or penalty
+    pub fn average() -> Self {
+        Attributes {
+            strength: AVERAGE_ATTRIBUTE,
+            dexterity: AVERAGE_ATTRIBUTE,
+            constitution: AVERAGE_ATTRIBUTE,
+            intelligence: AVERAGE_ATTRIBUTE,
+            wisdom: AVERAGE_ATTRIBUTE,
+        }
+    }
+
+    // all zero, used for equipment with no attribute bonus or requirement
+    pub const fn none() -> Self {
+        Attributes {
+            strength: 0,
+            dexterity: 0,
+            constitution: 0,
+            intelligence: 0,
+            wisdom: 0,
+        }
+    }
+
+    // true if every attribute is at least as high as the other
+    pub fn meets(&self, required: &Attributes) -> bool {
+        self.strength >= required.strength
+            && self.dexterity >= required.dexterity
+            && self.constitution >= required.constitution
+            && self.intelligence >= required.intelligence
+            && self.wisdom >= required.wisdom
+    }
+}
+
+impl std::ops::Add for Attributes {
+    type Output = Attributes;
+
+    fn add(self, other: Attributes) -> Attributes {
+        Attributes {
+            strength: self.strength + other.strength,
+            dexterity: self.dexterity + other.dexterity,
+            constitution: self.constitution + other.constitution,
+            intelligence: self.intelligence + other.intelligence,
+            wisdom: self.wisdom + other.wisdom,
+        }
+    }
+}
+
+// bonus (or penalty) an attribute value gives to derived stats
+pub fn modifier(value: i32) -> i32 {
+    value - AVERAGE_ATTRIBUTE
 }
 
 
@@ -255,6 +507,7 @@ pub struct Fighter {
 pub enum DeathCallback {
     Player,
     Monster,
+    Spawner,
 }
 
 impl DeathCallback {
@@ -265,6 +518,7 @@ impl DeathCallback {
         let callback = match self {
             Player => player_death,
             Monster => monster_death,
+            Spawner => spawner_death,
         };
         // call the appropriate function
         callback(object, game);
@@ -299,6 +553,29 @@ pub fn move_towards(id: usize, target_x: i32, target_y: i32, map: &Map, objects:
     move_by(id, dx, dy, map, objects);
 }
 
+// function to step away from a position (usually monster fleeing the player)
+pub fn move_away(id: usize, from_x: i32, from_y: i32, map: &Map, objects: &mut [Object]) {
+    let (x, y) = objects[id].pos();
+    let mut best_step = None;
+    let mut best_dist = objects[id].distance(from_x, from_y);
+    // pick the open neighbouring tile furthest from the position
+    for dx in -1..2 {
+        for dy in -1..2 {
+            if is_blocked(x + dx, y + dy, map, objects) {
+                continue;
+            }
+            let dist = (((x + dx - from_x).pow(2) + (y + dy - from_y).pow(2)) as f32).sqrt();
+            if dist > best_dist {
+                best_step = Some((dx, dy));
+                best_dist = dist;
+            }
+        }
+    }
+    if let Some((dx, dy)) = best_step {
+        move_by(id, dx, dy, map, objects);
+    }
+}
+
 // function to check if a tile is blocked by an blocking object
 pub fn is_blocked(x: i32, y: i32, map: &Map, objects: &[Object]) -> bool {
     // first test map tile
@@ -311,7 +588,60 @@ pub fn is_blocked(x: i32, y: i32, map: &Map, objects: &[Object]) -> bool {
 }
 
 
+// function to check that no walls block sight between two positions
+pub fn in_line_of_sight(start: (i32, i32), end: (i32, i32), map: &Map) -> bool {
+    Line::new(start, end)
+        .take_while(|&pos| pos != end)
+        .all(|(x, y)| !map[x as usize][y as usize].block_sight)
+}
+
+// function to follow a line of fire from start toward target
+// returns where the projectile lands and the fighter it hit, if any
+pub fn projectile_path(
+    start: (i32, i32),
+    target: (i32, i32),
+    map: &Map,
+    objects: &[Object],
+) -> (i32, i32, Option<usize>) {
+    let (mut x, mut y) = start;
+    // line does not include the start tile, so thrower can't hit themselves
+    for (next_x, next_y) in Line::new(start, target) {
+        // walls stop the projectile on the tile before them
+        if map[next_x as usize][next_y as usize].blocked {
+            break;
+        }
+        x = next_x;
+        y = next_y;
+        // first fighter in the way takes the hit
+        let hit_id = objects
+            .iter()
+            .position(|object| object.fighter.is_some() && object.pos() == (x, y));
+        if hit_id.is_some() {
+            return (x, y, hit_id);
+        }
+    }
+    (x, y, None)
+}
+
+
 pub fn player_move_or_attack(dx: i32, dy: i32, game: &mut Game, objects: &mut [Object]) {
+    // a confused player stumbles off in a random direction
+    let (dx, dy) = if objects[PLAYER].confused_turns > 0 {
+        objects[PLAYER].confused_turns -= 1;
+        if objects[PLAYER].confused_turns == 0 {
+            game.messages.add("Your head clears.", LIGHT_GREEN);
+        }
+        // re-roll a zero step, the player would otherwise end up targeting itself
+        loop {
+            let step = (rand::thread_rng().gen_range(-1, 2), rand::thread_rng().gen_range(-1, 2));
+            if step != (0, 0) {
+                break step;
+            }
+        }
+    } else {
+        (dx, dy)
+    };
+
     // coordinates player is moving too
     let x = objects[PLAYER].x + dx;
     let y = objects[PLAYER].y + dy;
@@ -321,30 +651,79 @@ pub fn player_move_or_attack(dx: i32, dy: i32, game: &mut Game, objects: &mut [O
 
     // attack if target found, move otherwise
     match target_id {
+        Some(target_id) if objects[target_id].faction() == Faction::Player => {
+            // swap places with allies instead of attacking them
+            let (player, ally) = mut_two(PLAYER, target_id, objects);
+            let player_pos = player.pos();
+            player.set_pos(ally.x, ally.y);
+            ally.set_pos(player_pos.0, player_pos.1);
+        }
+        Some(target_id) if matches!(objects[target_id].ai, Some(Ai::ForHire { .. })) => {
+            if let Some(Ai::ForHire { price }) = objects[target_id].ai {
+                hire(target_id, price, game, objects);
+            }
+        }
         Some(target_id) => {
+            // remember the target so allies can join in
+            game.player_target = Some((x, y));
+
+            // each swing trains the skill for the weapon in hand
+            let skill = objects[PLAYER].melee_skill(game);
+            train(skill, game);
+
             let (player, target) = mut_two(PLAYER, target_id, objects);
-            player.attack(target, game);
+            let mut kill_xp = player.attack(target, game);
+
+            // double strike gets a second swing at a target still standing
+            if target.fighter.is_some()
+                && player.has_perk(Perk::DoubleStrike, game)
+                && rand::thread_rng().gen_range(0, 100) < DOUBLE_STRIKE_CHANCE
+            {
+                game.messages.add("You strike again!", LIGHT_YELLOW);
+                kill_xp = player.attack(target, game);
+            }
+
+            // cleave carries a killing blow on to another adjacent enemy
+            if let Some(xp) = kill_xp {
+                award_xp(PLAYER, xp, game, objects);
+                if objects[PLAYER].has_perk(Perk::Cleave, game) {
+                    let next_id = objects.iter().position(|object| {
+                        objects[PLAYER].is_hostile_to(object) && objects[PLAYER].distance_to(object) < 2.0
+                    });
+                    if let Some(next_id) = next_id {
+                        game.messages.add("You cleave through to another foe!", LIGHT_YELLOW);
+                        let (player, next_target) = mut_two(PLAYER, next_id, objects);
+                        if let Some(xp) = player.attack(next_target, game) {
+                            award_xp(PLAYER, xp, game, objects);
+                        }
+                    }
+                }
+            }
         }
         None => {
             move_by(PLAYER, dx, dy, &game.map, objects);
+            let (x, y) = objects[PLAYER].pos();
+            make_noise(x, y, WALK_NOISE, game);
         }
     }
 }
 
 
 // funtion to find the closest monster object to the player -- returns index of the monster
-pub fn closest_monster(tcod: &Tcod, objects: &[Object], max_range: i32) -> Option<usize> {
+pub fn closest_monster(tcod: &Tcod, map: &Map, objects: &[Object], max_range: i32) -> Option<usize> {
     let mut closest_enemy = None;
-    let mut closest_dist = (max_range + 1) as f32; // start with slightly more than max range
-    
+    let mut closest_dist = UNREACHABLE;
+    // something just round a corner is further away than it looks
+    let distances = DijkstraMap::walking(&[objects[PLAYER].pos()], map, objects);
+
     for (id, object) in objects.iter().enumerate() {
         if (id != PLAYER) 
-            && object.fighter.is_some()
-            && object.ai.is_some()
+            && objects[PLAYER].is_hostile_to(object)
             && tcod.fov.is_in_fov(object.x, object.y) 
+            && objects[PLAYER].distance_to(object) < (max_range + 1) as f32
         {
-            // calculate distance between object and player 
-            let dist = objects[PLAYER].distance_to(object);
+            // calculate walking distance between object and player 
+            let dist = distances.distance(object.x, object.y);
             if dist < closest_dist {
                 // it is closer than previous closest so replace 
                 closest_enemy = Some(id);
@@ -357,6 +736,36 @@ pub fn closest_monster(tcod: &Tcod, objects: &[Object], max_range: i32) -> Optio
 }
 
 
+// function to find the closest object hostile to a faction near a position -- returns index of the object
+pub fn closest_hostile(faction: Faction, from: (i32, i32), objects: &[Object], max_range: f32) -> Option<usize> {
+    let mut closest_enemy = None;
+    let mut closest_dist = max_range;
+
+    for (id, object) in objects.iter().enumerate() {
+        if object.fighter.is_some() && object.alive && faction.is_hostile_to(object.faction()) {
+            let dist = object.distance(from.0, from.1);
+            if dist <= closest_dist {
+                closest_enemy = Some(id);
+                closest_dist = dist;
+            }
+        }
+    }
+    closest_enemy
+}
+
+
+// function to give xp for a kill -- only kills made by the player's side count towards levelling
+pub fn award_xp(killer_id: usize, xp: i32, game: &mut Game, objects: &mut [Object]) {
+    if objects[killer_id].faction() != Faction::Player {
+        return;
+    }
+    if let Some(fighter) = objects[PLAYER].fighter.as_mut() {
+        fighter.xp += xp;
+        game.messages.add(format!("You gain {} experience.", xp), ORANGE);
+    }
+}
+
+
 // function to split vector into 2 parts so you can borrow from 2 elements at the same time
 pub fn mut_two<T>(first_index: usize, second_index: usize, items: &mut [T]) -> (&mut T, &mut T) {
     // make sure the indices aren't the same
@@ -390,10 +799,16 @@ fn player_death(player: &mut Object, game: &mut Game) {
 fn monster_death(monster: &mut Object, game: &mut Game) {
     // transform it into corpse, it also doesn't block anymore
     // can't be attacked or move 
-    game.messages.add(
-        format!("{} is dead! You gain {} experience", monster.name, monster.fighter.unwrap().xp), 
-        ORANGE,
-    );
+    if monster.faction == Faction::Player {
+        game.messages.add(format!("Your {} has died!", monster.name), RED);
+    } else {
+        game.messages.add(format!("{} is dead!", monster.name), ORANGE);
+    }
+
+    // leave behind whatever it was carrying
+    drop_loot(monster, game);
+    drop_inventory(monster, game);
+    boss_death(monster, game);
 
     monster.char = '%';
     monster.color = DARK_RED;
@@ -419,17 +834,20 @@ pub fn level_up(tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
             format!("Your skills have increased! You are now level {}!", player.level),
             YELLOW,
         );
-        // increase player stats based on player choice
+        // increase player attributes based on player choice
         let fighter = player.fighter.as_mut().unwrap();
+        let attributes = fighter.attributes;
         let mut choice = None;
         while choice.is_none() {
             // keep asking until choice made
             choice = menu(
-                "Level up! Choose skill to increase:\n",
+                "Level up! Choose attribute to increase:\n",
                 &[
-                    format!("Constitution (+20 HP, from {})", fighter.base_max_hp),
-                    format!("Strength (+1 attack, from {})", fighter.base_power),
-                    format!("Agility (+1  defense, from {})", fighter.base_defense),
+                    format!("Strength (+1 attack, from {})", attributes.strength),
+                    format!("Dexterity (+{}% evasion, from {})", EVASION_PER_DEX, attributes.dexterity),
+                    format!("Constitution (+{} HP, from {})", HP_PER_CON, attributes.constitution),
+                    format!("Intelligence (+1 spell power, from {})", attributes.intelligence),
+                    format!("Wisdom (+1/2 spell power, from {})", attributes.wisdom),
                 ],
                 LEVEL_SCREEN_WIDTH,
                 &mut tcod.root,
@@ -439,17 +857,28 @@ pub fn level_up(tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
 
         match choice.unwrap() {
             0 => {
-                fighter.base_max_hp += 20;
-                fighter.hp += 20;
+                fighter.attributes.strength += 1;
             }
             1 => {
-                fighter.base_power += 1;
+                fighter.attributes.dexterity += 1;
             }
             2 => {
-                fighter.base_defense += 1;
+                fighter.attributes.constitution += 1;
+                fighter.hp += HP_PER_CON;
+            }
+            3 => {
+                fighter.attributes.intelligence += 1;
+            }
+            4 => {
+                fighter.attributes.wisdom += 1;
             }
             _ => unreachable!(),
         }
+
+        // every few levels also pick a perk
+        if player.level % PERK_LEVEL_INTERVAL == 0 {
+            choose_perk(tcod, game, objects);
+        }
     }
 }
 
@@ -0,0 +1,154 @@
+use std::cmp::{self, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::map::Map;
+use crate::object::{Object, move_by, move_towards};
+
+pub const STRAIGHT_COST: i32 = 10;
+pub const DIAGONAL_COST: i32 = 14;
+pub const OCCUPIED_COST: i32 = 50;   // walking around a crowd is better, but waiting behind it is allowed
+const MAX_SEARCH: usize = 2000;  // tiles to look at before giving up on finding a path
+
+// estimate of the cost between two tiles when moving in eight directions
+fn heuristic((x1, y1): (i32, i32), (x2, y2): (i32, i32)) -> i32 {
+    let (dx, dy) = ((x1 - x2).abs(), (y1 - y2).abs());
+    STRAIGHT_COST * cmp::max(dx, dy) + (DIAGONAL_COST - STRAIGHT_COST) * cmp::min(dx, dy)
+}
+
+// find the cheapest path from start to goal with A*, not including the start tile
+// tiles with something blocking standing on them cost more instead of being walls
+pub fn find_path(start: (i32, i32), goal: (i32, i32), map: &Map, objects: &[Object]) -> Option<Vec<(i32, i32)>> {
+    let (width, height) = (map.len() as i32, map[0].len() as i32);
+    let index = |(x, y): (i32, i32)| (x * height + y) as usize;
+
+    let mut occupied = vec![false; (width * height) as usize];
+    for object in objects.iter().filter(|object| object.blocks) {
+        occupied[index(object.pos())] = true;
+    }
+
+    let mut cost = vec![i32::MAX; (width * height) as usize];
+    let mut came_from = vec![None; (width * height) as usize];
+    let mut open = BinaryHeap::new();
+    cost[index(start)] = 0;
+    open.push(Reverse((heuristic(start, goal), start)));
+
+    let mut searched = 0;
+    while let Some(Reverse((_, pos))) = open.pop() {
+        if pos == goal {
+            // walk back from the goal to get the path
+            let mut path = vec![goal];
+            let mut current = goal;
+            while let Some(previous) = came_from[index(current)] {
+                if previous == start {
+                    break;
+                }
+                path.push(previous);
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        searched += 1;
+        if searched > MAX_SEARCH {
+            return None;
+        }
+
+        for dx in -1..2 {
+            for dy in -1..2 {
+                let next = (pos.0 + dx, pos.1 + dy);
+                if (dx, dy) == (0, 0)
+                    || next.0 < 0 || next.1 < 0 || next.0 >= width || next.1 >= height
+                    || map[next.0 as usize][next.1 as usize].blocked
+                {
+                    continue;
+                }
+                let step = if dx != 0 && dy != 0 { DIAGONAL_COST } else { STRAIGHT_COST };
+                // the goal is usually occupied by whatever is being chased
+                let crowd = if next != goal && occupied[index(next)] { OCCUPIED_COST } else { 0 };
+                let new_cost = cost[index(pos)] + step + crowd;
+                if new_cost < cost[index(next)] {
+                    cost[index(next)] = new_cost;
+                    came_from[index(next)] = Some(pos);
+                    open.push(Reverse((new_cost + heuristic(next, goal), next)));
+                }
+            }
+        }
+    }
+    None
+}
+
+// function to take one step along the best path to a target, or straight at it if there is no path
+pub fn path_towards(id: usize, target_x: i32, target_y: i32, map: &Map, objects: &mut [Object]) {
+    let (x, y) = objects[id].pos();
+    let first_step = find_path((x, y), (target_x, target_y), map, objects).and_then(|path| path.first().copied());
+    match first_step {
+        // if the step is taken by someone, wait for them to move
+        Some((step_x, step_y)) => move_by(id, step_x - x, step_y - y, map, objects),
+        None => move_towards(id, target_x, target_y, map, objects),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Tile;
+    use tcod::colors::WHITE;
+
+    // open map with walls at the given tiles
+    fn map_with_walls(width: usize, height: usize, walls: &[(usize, usize)]) -> Map {
+        let mut map = vec![vec![Tile::empty(); height]; width];
+        for &(x, y) in walls {
+            map[x][y] = Tile::wall();
+        }
+        map
+    }
+
+    // check each step of a path is one tile from the last and not in a wall
+    fn assert_walkable(start: (i32, i32), path: &[(i32, i32)], map: &Map) {
+        let mut previous = start;
+        for &(x, y) in path {
+            assert!((x - previous.0).abs() <= 1 && (y - previous.1).abs() <= 1, "jump to {:?}", (x, y));
+            assert!(!map[x as usize][y as usize].blocked, "walks through wall at {:?}", (x, y));
+            previous = (x, y);
+        }
+    }
+
+    #[test]
+    fn goes_around_wall_corner() {
+        // wall down from the top edge, open only below it
+        let map = map_with_walls(7, 7, &[(3, 0), (3, 1), (3, 2), (3, 3), (3, 4)]);
+        let path = find_path((1, 1), (5, 1), &map, &[]).expect("a way around the wall");
+
+        assert_eq!(path.last(), Some(&(5, 1)));
+        assert_walkable((1, 1), &path, &map);
+        assert!(path.contains(&(3, 5)) || path.contains(&(3, 6)));
+    }
+
+    #[test]
+    fn avoids_occupied_tile_when_it_can() {
+        let map = map_with_walls(5, 5, &[]);
+        let blocker = Object::new(2, 2, 'o', "orc", WHITE, true);
+        let path = find_path((0, 2), (4, 2), &map, &[blocker]).expect("a path");
+
+        assert_eq!(path.last(), Some(&(4, 2)));
+        assert!(!path.contains(&(2, 2)));
+    }
+
+    #[test]
+    fn paths_through_occupied_tile_in_corridor() {
+        // one tile wide corridor along the middle row
+        let walls: Vec<(usize, usize)> = (0..5).flat_map(|x| [(x, 0), (x, 2)]).collect();
+        let map = map_with_walls(5, 3, &walls);
+        let blocker = Object::new(2, 1, 'o', "orc", WHITE, true);
+        let path = find_path((0, 1), (4, 1), &map, &[blocker]).expect("a path to wait along");
+
+        assert_eq!(path, vec![(1, 1), (2, 1), (3, 1), (4, 1)]);
+    }
+
+    #[test]
+    fn no_path_when_walled_off() {
+        let map = map_with_walls(5, 5, &[(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)]);
+        assert_eq!(find_path((0, 2), (4, 2), &map, &[]), None);
+    }
+}
@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::sync::OnceLock;
+use tcod::colors::*;
+use serde::{Deserialize, Serialize};
+
+use crate::menu::menu;
+use crate::object::{Object, Attributes};
+use crate::game::{Tcod, Game};
+use crate::monster::read_data_file;
+
+const PERK_FILE: &str = "../data/perks.json";
+pub const PERK_LEVEL_INTERVAL: i32 = 3; // offer a perk every 3 levels
+const PERK_SCREEN_WIDTH: i32 = 60;
+const PLAYER: usize = 0;
+
+// perk definitions, loaded once at startup
+static PERK_DEFS: OnceLock<Vec<PerkDef>> = OnceLock::new();
+
+// special abilities the player can pick at milestone levels
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Perk {
+    Toughness,
+    Cleave,
+    DoubleStrike,
+    SpellEfficiency,
+    Shadow,
+}
+
+// definition of a perk and what is needed to take it, read from the perk data file
+#[derive(Clone, Debug, Deserialize)]
+pub struct PerkDef {
+    pub perk: Perk,
+    pub name: String,
+    pub description: String,
+    pub min_level: i32,
+    #[serde(default)]
+    pub required_perk: Option<Perk>,
+    #[serde(default = "Attributes::none")]
+    pub required: Attributes,
+}
+
+/*
+ *  Loading perk definitions
+ */
+
+// function to read and check the perk data file, must be called once before any perks are offered
+pub fn load_perk_defs() -> Result<(), Box<dyn Error>> {
+    let defs: Vec<PerkDef> = read_data_file(PERK_FILE)?;
+    validate_perk_defs(&defs).map_err(|e| format!("{}: {}", PERK_FILE, e))?;
+    PERK_DEFS
+        .set(defs)
+        .map_err(|_| "perk definitions loaded twice")?;
+    Ok(())
+}
+
+// check for mistakes serde can't catch, returns a description of the first one found
+fn validate_perk_defs(defs: &[PerkDef]) -> Result<(), String> {
+    for (i, def) in defs.iter().enumerate() {
+        if defs[..i].iter().any(|other| other.perk == def.perk) {
+            return Err(format!("{:?} is defined more than once", def.perk));
+        }
+        if def.name.is_empty() {
+            return Err(format!("{:?} has an empty name", def.perk));
+        }
+        if def.min_level < 1 {
+            return Err(format!("'{}' min_level must be at least 1", def.name));
+        }
+        if let Some(required) = def.required_perk {
+            if required == def.perk {
+                return Err(format!("'{}' can't require itself", def.name));
+            }
+            if !defs.iter().any(|other| other.perk == required) {
+                return Err(format!("'{}' requires {:?}, which isn't defined", def.name, required));
+            }
+        }
+    }
+    Ok(())
+}
+
+// the pool of perks players can choose from
+pub fn perk_defs() -> &'static [PerkDef] {
+    PERK_DEFS.get().expect("perk definitions not loaded")
+}
+
+// check if the player meets everything needed to take a perk
+fn can_take(def: &PerkDef, player: &Object, game: &Game) -> bool {
+    !game.perks.contains(&def.perk)
+        && player.level >= def.min_level
+        && def.required_perk.is_none_or(|perk| game.perks.contains(&perk))
+        && player.attributes(game).meets(&def.required)
+}
+
+// function to let the player pick a new perk from the ones they qualify for
+pub fn choose_perk(tcod: &mut Tcod, game: &mut Game, objects: &[Object]) {
+    let available: Vec<&PerkDef> = perk_defs()
+        .iter()
+        .filter(|def| can_take(def, &objects[PLAYER], game))
+        .collect();
+
+    // nothing left to learn
+    if available.is_empty() {
+        return;
+    }
+
+    let options: Vec<String> = available
+        .iter()
+        .map(|def| format!("{} ({})", def.name, def.description))
+        .collect();
+
+    let mut choice = None;
+    while choice.is_none() {
+        // keep asking until choice made
+        choice = menu(
+            "You have reached a milestone! Choose a perk:\n",
+            &options,
+            PERK_SCREEN_WIDTH,
+            &mut tcod.root,
+        );
+    }
+
+    let def = available[choice.unwrap()];
+    game.perks.push(def.perk);
+    game.messages.add(format!("You have learned {}!", def.name), YELLOW);
+}
+
+// return display name of a perk
+pub fn perk_name(perk: Perk) -> &'static str {
+    perk_defs()
+        .iter()
+        .find(|def| def.perk == perk)
+        .map_or("", |def| &def.name)
+}
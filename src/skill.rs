@@ -0,0 +1,119 @@
+use tcod::colors::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::item::{Item, item_def};
+
+const SKILL_XP_BASE: i32 = 10; // uses needed for first skill level, grows each level
+const MAX_SKILL_LEVEL: i32 = 10;
+
+// proficiencies that improve by being used
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Skill {
+    Blades,
+    Blunt,
+    Ranged,
+    Evocation,
+    Stealth,
+}
+
+// implementing Display trait for Skill enum
+impl std::fmt::Display for Skill {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Skill::Blades => write!(f, "blades"),
+            Skill::Blunt => write!(f, "blunt"),
+            Skill::Ranged => write!(f, "ranged"),
+            Skill::Evocation => write!(f, "evocation"),
+            Skill::Stealth => write!(f, "stealth"),
+        }
+    }
+}
+
+// current level and progress in one skill
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Proficiency {
+    pub level: i32,
+    pub xp: i32,
+}
+
+// struct to hold all of the player's skills
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Skills {
+    blades: Proficiency,
+    blunt: Proficiency,
+    ranged: Proficiency,
+    evocation: Proficiency,
+    stealth: Proficiency,
+}
+
+impl Skills {
+    pub fn new() -> Self {
+        let untrained = Proficiency { level: 0, xp: 0 };
+        Skills {
+            blades: untrained,
+            blunt: untrained,
+            ranged: untrained,
+            evocation: untrained,
+            stealth: untrained,
+        }
+    }
+
+    pub fn get(&self, skill: Skill) -> Proficiency {
+        match skill {
+            Skill::Blades => self.blades,
+            Skill::Blunt => self.blunt,
+            Skill::Ranged => self.ranged,
+            Skill::Evocation => self.evocation,
+            Skill::Stealth => self.stealth,
+        }
+    }
+
+    fn get_mut(&mut self, skill: Skill) -> &mut Proficiency {
+        match skill {
+            Skill::Blades => &mut self.blades,
+            Skill::Blunt => &mut self.blunt,
+            Skill::Ranged => &mut self.ranged,
+            Skill::Evocation => &mut self.evocation,
+            Skill::Stealth => &mut self.stealth,
+        }
+    }
+}
+
+// all skills in display order
+pub const ALL_SKILLS: [Skill; 5] = [
+    Skill::Blades,
+    Skill::Blunt,
+    Skill::Ranged,
+    Skill::Evocation,
+    Skill::Stealth,
+];
+
+/*
+ * Skill related functions
+ */
+
+// function to give experience to a skill each time it is used, levels it up when enough is gained
+pub fn train(skill: Skill, game: &mut Game) {
+    let proficiency = game.skills.get_mut(skill);
+    if proficiency.level >= MAX_SKILL_LEVEL {
+        return;
+    }
+
+    proficiency.xp += 1;
+    let needed = SKILL_XP_BASE * (proficiency.level + 1);
+    if proficiency.xp >= needed {
+        proficiency.xp -= needed;
+        proficiency.level += 1;
+        let level = proficiency.level;
+        game.messages.add(
+            format!("Your {} skill increases to {}!", skill, level),
+            LIGHT_YELLOW,
+        );
+    }
+}
+
+// which skill a weapon trains, None if the item isn't a weapon
+pub fn weapon_skill(item: &Item) -> Option<Skill> {
+    item.kind().and_then(item_def).and_then(|def| def.skill)
+}
@@ -0,0 +1,229 @@
+use std::error::Error;
+use std::sync::OnceLock;
+use rand::Rng;
+use rand::distributions::{IndependentSample, Weighted, WeightedChoice};
+use tcod::colors::*;
+use serde::{Deserialize, Serialize};
+
+use crate::map::{Map, Rect};
+use crate::object::{Object, Fighter, DeathCallback, Attributes, from_dungeon_level, is_blocked};
+use crate::monster::{MonsterDef, monster_def, spawn_monster, read_data_file};
+use crate::faction::Faction;
+use crate::game::{Tcod, Game};
+
+const NEST_FILE: &str = "../data/nests.json";
+const SPAWNER_CHANCE: i32 = 35;   // percent chance a level has a spawner
+const SPAWNER_RANGE: f32 = 12.0;  // only produces monsters while the player is this close
+const PLAYER: usize = 0;
+
+// nest definitions, loaded once at startup
+static NEST_DEFS: OnceLock<Vec<NestDef>> = OnceLock::new();
+
+// what kind of nest an object is and when it next produces a monster
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnerState {
+    pub kind: String,
+    pub timer: i32,
+}
+
+// hand-designed definition of a kind of nest, read from the nest data file
+#[derive(Clone, Debug, Deserialize)]
+struct NestDef {
+    kind: String,  // id saved spawners refer to it by
+    name: String,
+    glyph: char,
+    color: Color,
+    faction: Faction,
+    monsters: Vec<String>,  // kinds that come out of it, weighted by the level's spawn table
+    max_hp: i32,
+    defense: i32,
+    xp: i32,
+    interval: i32,     // turns between new monsters
+    max_alive: usize,  // stops producing while this many of its monsters are alive
+}
+
+/*
+ *  Loading nest definitions
+ */
+
+// function to read and check the nest data file, must be called once before any spawners are made
+// and after the monster definitions, which nests are checked against
+pub fn load_nest_defs() -> Result<(), Box<dyn Error>> {
+    let defs: Vec<NestDef> = read_data_file(NEST_FILE)?;
+    validate_nest_defs(&defs).map_err(|e| format!("{}: {}", NEST_FILE, e))?;
+    NEST_DEFS
+        .set(defs)
+        .map_err(|_| "nest definitions loaded twice")?;
+    Ok(())
+}
+
+// check for mistakes serde can't catch, returns a description of the first one found
+fn validate_nest_defs(defs: &[NestDef]) -> Result<(), String> {
+    for (i, def) in defs.iter().enumerate() {
+        if def.kind.is_empty() {
+            return Err(format!("'{}' has an empty kind", def.name));
+        }
+        if defs[..i].iter().any(|other| other.kind == def.kind) {
+            return Err(format!("{} is defined more than once", def.kind));
+        }
+        if def.name.is_empty() {
+            return Err(format!("{} has an empty name", def.kind));
+        }
+        if def.max_hp <= 0 {
+            return Err(format!("'{}' must have max_hp above 0", def.name));
+        }
+        if def.xp < 0 {
+            return Err(format!("'{}' can't give negative xp", def.name));
+        }
+        if def.interval <= 0 || def.max_alive == 0 {
+            return Err(format!("'{}' must have interval and max_alive above 0", def.name));
+        }
+        if def.monsters.is_empty() {
+            return Err(format!("'{}' has no monsters", def.name));
+        }
+        for kind in &def.monsters {
+            if monster_def(kind).is_none() {
+                return Err(format!("'{}' makes {}, which isn't defined", def.name, kind));
+            }
+        }
+    }
+    Ok(())
+}
+
+// all loaded nest definitions
+fn nest_defs() -> &'static [NestDef] {
+    NEST_DEFS.get().expect("nest definitions not loaded")
+}
+
+/*
+ *  Spawner related functions
+ */
+
+// definition for one kind of nest
+fn nest_def(kind: &str) -> Option<&'static NestDef> {
+    nest_defs().iter().find(|def| def.kind == kind)
+}
+
+// the level's spawn table cut down to the monsters that come out of a nest
+fn nest_table(def: &NestDef, level: u32) -> Vec<Weighted<&'static MonsterDef>> {
+    def.monsters
+        .iter()
+        .filter_map(|kind| monster_def(kind))
+        .map(|monster| Weighted {
+            weight: from_dungeon_level(&monster.spawn_chance, level),
+            item: monster,
+        })
+        .filter(|chance| chance.weight > 0)
+        .collect()
+}
+
+// function to maybe put a spawner in a room, only a kind that has monsters to produce on this level
+pub fn place_spawner(room: Rect, map: &Map, objects: &mut Vec<Object>, level: u32) {
+    if rand::thread_rng().gen_range(0, 100) >= SPAWNER_CHANCE {
+        return;
+    }
+    let nests: Vec<&NestDef> = nest_defs()
+        .iter()
+        .filter(|def| !nest_table(def, level).is_empty())
+        .collect();
+    if nests.is_empty() {
+        return;
+    }
+    let def = nests[rand::thread_rng().gen_range(0, nests.len())];
+
+    let (x, y) = room.center();
+    if is_blocked(x, y, map, objects) {
+        return;
+    }
+
+    let mut spawner = Object::new(x, y, def.glyph, &def.name, def.color, true);
+    spawner.fighter = Some(Fighter {
+        base_max_hp: def.max_hp,
+        hp: def.max_hp,
+        base_defense: def.defense,
+        base_power: 0,
+        base_magic: 0,
+        base_stealth: 0,
+        xp: def.xp,
+        on_death: DeathCallback::Spawner,
+        attributes: Attributes::average(),
+    });
+    spawner.faction = def.faction;
+    spawner.spawner = Some(SpawnerState { kind: def.kind.clone(), timer: def.interval });
+    // its monsters share its group, so it knows how many are still alive
+    spawner.group = Some(objects.len());
+    spawner.always_visible = true;
+    spawner.alive = true;
+    objects.push(spawner);
+}
+
+// function to let every spawner near the player count down and produce a monster, called once a turn
+pub fn tick_spawners(tcod: &Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    for id in 0..objects.len() {
+        if !objects[id].alive || objects[id].distance_to(&objects[PLAYER]) > SPAWNER_RANGE {
+            continue;
+        }
+        let state = match objects[id].spawner.as_mut() {
+            Some(state) => state,
+            None => continue,
+        };
+        let def = match nest_def(&state.kind) {
+            Some(def) => def,
+            None => continue,
+        };
+        if state.timer > 1 {
+            state.timer -= 1;
+            continue;
+        }
+        state.timer = def.interval;
+
+        // stop while enough of its brood is still around
+        let group = objects[id].group;
+        let alive = objects
+            .iter()
+            .filter(|object| object.group == group && object.alive && object.spawner.is_none())
+            .count();
+        if alive >= def.max_alive {
+            continue;
+        }
+        spawn_from_nest(id, def, tcod, game, objects);
+    }
+}
+
+// function to put a new monster from a nest's table on a free tile next to it
+fn spawn_from_nest(spawner_id: usize, def: &NestDef, tcod: &Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    let table = &mut nest_table(def, game.dungeon_level);
+    if table.is_empty() {
+        return;
+    }
+    let monster_def = WeightedChoice::new(table).ind_sample(&mut rand::thread_rng());
+
+    let (x, y) = objects[spawner_id].pos();
+    let free_spot = (-1..2)
+        .flat_map(|dx| (-1..2).map(move |dy| (x + dx, y + dy)))
+        .find(|&(x, y)| !is_blocked(x, y, &game.map, objects));
+    // no room means nothing comes out this time
+    if let Some((spot_x, spot_y)) = free_spot {
+        let mut monster = spawn_monster(monster_def, spot_x, spot_y, game.dungeon_level);
+        monster.group = objects[spawner_id].group;
+        monster.alive = true;
+        if tcod.fov.is_in_fov(spot_x, spot_y) {
+            game.messages.add(
+                format!("A {} crawls out of the {}!", monster.name, objects[spawner_id].name),
+                ORANGE,
+            );
+        }
+        objects.push(monster);
+    }
+}
+
+// function for when a spawner is destroyed, it leaves rubble behind
+pub fn spawner_death(spawner: &mut Object, game: &mut Game) {
+    game.messages.add(format!("The {} is destroyed!", spawner.name), ORANGE);
+    spawner.char = '%';
+    spawner.color = DARK_GREY;
+    spawner.blocks = false;
+    spawner.fighter = None;
+    spawner.spawner = None;
+    spawner.name = format!("ruins of {}", spawner.name);
+}
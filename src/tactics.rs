@@ -0,0 +1,128 @@
+use tcod::colors::*;
+
+use crate::map::Map;
+use crate::object::{Object, move_by, is_blocked};
+use crate::monster_ai::{alert, is_unaware};
+use crate::pathfinding::{find_path, path_towards};
+use crate::dijkstra::DijkstraMap;
+use crate::ability::can_heal;
+use crate::game::{Tcod, Game};
+
+const GROUP_RANGE: f32 = 6.0;    // group members this close wait for each other outside narrow spots
+const HEALER_RANGE: f32 = 3.0;   // healers try to stay this close to hurt group members
+const HELP_RANGE: f32 = 10.0;    // how far a call for help carries
+
+/*
+ *  Group tactics
+ */
+
+// every other living member of a monster's group
+pub fn group_members(monster_id: usize, objects: &[Object]) -> Vec<usize> {
+    let group = match objects[monster_id].group {
+        Some(group) => group,
+        None => return vec![],
+    };
+    // a charmed or confused member isn't on the group's side for now
+    let faction = objects[monster_id].faction();
+    objects
+        .iter()
+        .enumerate()
+        .filter(|&(id, object)| {
+            id != monster_id
+                && object.group == Some(group)
+                && object.faction() == faction
+                && object.alive
+                && object.ai.is_some()
+        })
+        .map(|(id, _)| id)
+        .collect()
+}
+
+// function to move a group member towards its target working with the rest of the group
+// returns true if it did something, false to let it move on its own
+pub fn group_move(monster_id: usize, target_id: usize, game: &Game, objects: &mut [Object]) -> bool {
+    let members = group_members(monster_id, objects);
+    if members.is_empty() {
+        return false;
+    }
+
+    // healers stay close to hurt friends instead of charging in
+    if can_heal(&objects[monster_id]) {
+        let hurt_friend = members.iter().copied().find(|&id| {
+            objects[id].fighter.is_some_and(|f| f.hp * 2 < objects[id].max_hp(game))
+                && objects[monster_id].distance_to(&objects[id]) > HEALER_RANGE
+        });
+        if let Some(friend_id) = hurt_friend {
+            let (x, y) = objects[friend_id].pos();
+            path_towards(monster_id, x, y, &game.map, objects);
+            return true;
+        }
+    }
+
+    let (x, y) = objects[monster_id].pos();
+    let (target_x, target_y) = objects[target_id].pos();
+
+    // don't follow the target into a corridor or doorway one at a time, wait for it to come out
+    let friends_close = members
+        .iter()
+        .any(|&id| objects[monster_id].distance_to(&objects[id]) <= GROUP_RANGE);
+    if friends_close && is_narrow(target_x, target_y, &game.map) && !is_narrow(x, y, &game.map) {
+        let next_step = find_path((x, y), (target_x, target_y), &game.map, objects)
+            .and_then(|path| path.first().copied());
+        if let Some((step_x, step_y)) = next_step {
+            if (step_x, step_y) != (target_x, target_y) && is_narrow(step_x, step_y, &game.map) {
+                return true;
+            }
+        }
+    }
+
+    // spread out to the free tiles around the target instead of queueing up behind each other
+    let free_spots: Vec<(i32, i32)> = (-1..2)
+        .flat_map(|dx| (-1..2).map(move |dy| (target_x + dx, target_y + dy)))
+        .filter(|&(spot_x, spot_y)| !is_blocked(spot_x, spot_y, &game.map, objects))
+        .collect();
+    if free_spots.is_empty() {
+        return false;
+    }
+    let surround_map = DijkstraMap::walking(&free_spots, &game.map, objects);
+    match surround_map.downhill(x, y, &game.map, objects) {
+        Some((step_x, step_y)) => {
+            move_by(monster_id, step_x - x, step_y - y, &game.map, objects);
+            true
+        }
+        None => false,
+    }
+}
+
+// check if a tile is a corridor or doorway, with walls on two opposite sides
+fn is_narrow(x: i32, y: i32, map: &Map) -> bool {
+    let wall = |x: i32, y: i32| {
+        x < 0 || y < 0 || x >= map.len() as i32 || y >= map[0].len() as i32 || map[x as usize][y as usize].blocked
+    };
+    (wall(x - 1, y) && wall(x + 1, y)) || (wall(x, y - 1) && wall(x, y + 1))
+}
+
+// function for a badly hurt monster to wake up anyone on its side within earshot
+pub fn call_for_help(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) {
+    let monster = &objects[monster_id];
+    let hurt = monster.fighter.is_some_and(|f| f.hp * 2 < monster.max_hp(game));
+    if !monster.alive || !hurt || monster.ai.as_ref().is_none_or(is_unaware) {
+        return;
+    }
+
+    let (faction, x, y) = (monster.faction(), monster.x, monster.y);
+    let mut helped = false;
+    for object in objects.iter_mut() {
+        if object.faction() == faction
+            && object.alive
+            && object.distance(x, y) <= HELP_RANGE
+            && object.ai.as_ref().is_some_and(is_unaware)
+        {
+            object.ai = object.ai.take().map(alert);
+            helped = true;
+        }
+    }
+    if helped && tcod.fov.is_in_fov(x, y) {
+        game.messages.add(format!("The {} shouts for help!", objects[monster_id].name), ORANGE);
+    }
+}